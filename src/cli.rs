@@ -1,10 +1,22 @@
+use chrono_tz::Tz;
 use clap::Parser;
-use std::path::Path;
-use std::io::BufWriter;
-use crate::{conversion, io};
-use crate::io::EventLogColumns;
-use crate::xes::interval::EventLog;
-use crate::xes::lifecycle;
+use encoding_rs::Encoding;
+use std::path::{Path, PathBuf};
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Seek, Write};
+use std::rc::Rc;
+use crate::compression::{self, Compression};
+use crate::conversion::{ConversionOptions, PairBy, UnpairedPolicy};
+use crate::error::{Error, Result};
+use crate::format::{
+    self, CsvFormat, Format, FormatRegistry, InputSource, MxmlFormat, OcelFormat, ParquetFormat, XesFormat,
+};
+use crate::ocel::{ObjectMapping, OcelSyntax};
+use crate::parquet_io;
+use crate::timestamp::TimestampFormat;
+use crate::config::{ColumnNames, Config, DialectConfig};
+use crate::io::{ColumnPreset, CsvShape, EventLogColumns};
 
 /// Event log converter
 #[derive(Parser, Debug)]
@@ -13,87 +25,553 @@ pub struct Args {
     #[command(subcommand)]
     pub action: Action,
 
-    /// The input event log path
-    #[arg(short, long)]
-    pub input_log: String,
+    /// The input event log path of xes-to-csv and csv-to-xes, or "-" for standard input
+    #[arg(short, long, global = true)]
+    pub input_log: Option<String>,
 
-    /// The output directory for the converted log
-    #[arg(short, long, default_value = ".")]
+    /// The output directory for the converted log of xes-to-csv and csv-to-xes, or "-" for standard output
+    #[arg(short, long, default_value = ".", global = true)]
     pub output_dir: String,
 
     /// Filter out Start and End events if present in the log
-    #[arg(short, long, default_value = "false")]
+    #[arg(short, long, default_value = "false", global = true)]
     pub no_start_events: bool,
 
-    /// Case ID column name
-    #[arg(short, long, default_value = "case")]
-    pub case: String,
+    /// CSV column names of a process mining tool. Without a preset or column names, csv-to-xes detects the preset
+    /// from the CSV header
+    #[arg(long, value_enum, global = true)]
+    pub preset: Option<ColumnPreset>,
 
-    /// Activity column name
-    #[arg(short, long, default_value = "activity")]
-    pub activity: String,
+    /// Input format: xes, mxml, ocel-json, ocel-xml, parquet or csv, for input whose format cannot be inferred from its file
+    /// name or content
+    #[arg(long, global = true)]
+    pub from: Option<String>,
 
-    /// Resource column name
-    #[arg(short, long, default_value = "resource")]
-    pub resource: String,
+    /// Output format: xes, mxml, ocel-json, ocel-xml, parquet or csv, for output whose format cannot be inferred from its
+    /// file name, such as standard output
+    #[arg(long, global = true)]
+    pub to: Option<String>,
 
-    /// Start timestamp column name
-    #[arg(short, long, default_value = "start_time")]
-    pub start_time: String,
+    /// Compress the output. Defaults to the compression of the output file extension, .gz or .zst. Compressed input
+    /// is recognized by its content
+    #[arg(long, value_enum, global = true)]
+    pub compress: Option<Compression>,
 
-    /// End timestamp column name
-    #[arg(short, long, default_value = "end_time")]
-    pub end_time: String,
+    #[command(flatten)]
+    pub columns: ColumnNames,
 
-    /// Variant column name
-    #[arg(short, long, default_value = "variant")]
-    pub variant: String,
+    /// How events are laid out in CSV rows: interval rows with start and end time, lifecycle rows with a lifecycle
+    /// column, or atomic rows that are all complete events. With a preset, csv-to-xes picks the shape whose columns
+    /// are in the CSV header [default: interval]
+    #[arg(long, value_enum, global = true)]
+    pub csv_shape: Option<CsvShape>,
+
+    /// CSV field delimiter, e.g. ";" or "tab" [default: ,]
+    #[arg(long, value_parser = parse_char, global = true)]
+    pub delimiter: Option<u8>,
+
+    /// CSV quote character [default: "]
+    #[arg(long, value_parser = parse_char, global = true)]
+    pub quote: Option<u8>,
+
+    /// CSV escape character for quotes in quoted fields. Quotes are escaped by doubling them if not set
+    #[arg(long, value_parser = parse_char, global = true)]
+    pub escape: Option<u8>,
+
+    /// The CSV has no header row. Columns are then mapped by position starting at 1, e.g. "-c 1 -a 2"
+    #[arg(long, default_value = "false", global = true)]
+    pub no_header: bool,
+
+    /// CSV input encoding, e.g. windows-1252. Defaults to UTF-8 with or without byte order mark
+    #[arg(long, value_parser = parse_encoding, global = true)]
+    pub encoding: Option<&'static Encoding>,
+
+    /// Indent written XES, MXML and OCEL by this many spaces per level, with one element per line
+    #[arg(long, value_name = "SPACES", global = true)]
+    pub indent: Option<usize>,
+
+    /// Rows per row group of written Parquet files, which bounds the memory used to write them
+    #[arg(long, value_name = "ROWS", default_value_t = parquet_io::DEFAULT_ROW_GROUP_SIZE, global = true)]
+    pub row_group_size: usize,
+
+    /// OCEL object type of the cases: OCEL input is flattened onto its objects, and cases are written as objects of
+    /// this type
+    #[arg(long, value_name = "TYPE", default_value = "case", global = true)]
+    pub object_type: String,
+
+    /// OCEL object type of the resources that events relate to
+    #[arg(long, value_name = "TYPE", default_value = "resource", global = true)]
+    pub resource_type: String,
+
+    /// Number the distinct activity sequences as variants for traces without a variant
+    #[arg(long, default_value = "false", global = true)]
+    pub compute_variants: bool,
+
+    /// CSV timestamp format: iso8601, epoch-seconds, epoch-millis or a pattern like "%d.%m.%Y %H:%M". Repeat to
+    /// try several formats in order [default: iso8601]
+    #[arg(short, long, global = true)]
+    pub timestamp_format: Vec<TimestampFormat>,
+
+    /// Timezone of timestamps without an offset, as an IANA name like "Europe/Berlin" [default: UTC]
+    #[arg(long, global = true)]
+    pub input_timezone: Option<Tz>,
+
+    /// Convert all timestamps to this timezone, as an IANA name or "UTC". Offsets are kept as they are if not set
+    #[arg(long, global = true)]
+    pub output_timezone: Option<Tz>,
+
+    /// Pair start and complete events by activity, or also by resource or concept:instance
+    #[arg(long, value_enum, default_value_t = PairBy::Activity, global = true)]
+    pub pair_by: PairBy,
+
+    /// What to do with start events without a complete event and vice versa, and with unknown lifecycle transitions
+    #[arg(long, value_enum, default_value_t = UnpairedPolicy::Error, global = true)]
+    pub unpaired: UnpairedPolicy,
+
+    /// TOML, YAML or JSON file with the column mapping, timestamp formats, timezones, CSV dialect and column names
+    /// of extra attributes. Command line flags take precedence over it
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+
+    /// Save the effective column mapping, timestamp formats, timezones and CSV dialect to a TOML, YAML or JSON file
+    #[arg(long, global = true)]
+    pub save_config: Option<String>,
 }
 
 #[derive(clap::Subcommand, Debug)]
 pub enum Action {
+    /// Convert an event log to another format, inferred from the file extensions or the input content
+    Convert {
+        /// The input event log path, or "-" for standard input
+        input: String,
+
+        /// The output event log path, or "-" for standard output
+        output: String,
+    },
+
+    /// Convert the XES input log to CSV in the output directory
     XesToCsv,
+
+    /// Convert the CSV input log to XES in the output directory
     CsvToXes,
 }
 
-pub fn convert_xes_to_csv(args: &Args) {
-    let input_log = Path::new(&args.input_log);
-    let output_dir = Path::new(&args.output_dir);
+/// Process exit code for a failed conversion, so scripts can tell error kinds apart.
+pub fn exit_code(error: &Error) -> i32 {
+    match error {
+        Error::Io(_) => 2,
+        Error::Xml { .. } | Error::InvalidAttribute { .. } | Error::Ocel { .. } => 3,
+        Error::Csv { .. } => 4,
+        Error::MissingColumn { .. } | Error::MissingObjectType { .. } | Error::UndeclaredColumn { .. } => 5,
+        Error::UnpairedEvents { .. } => 6,
+        Error::InvalidTimestamp { .. } | Error::LocalTime { .. } => 7,
+        Error::Config { .. } => 8,
+        Error::UnknownFormat { .. } | Error::UnsupportedFormat { .. } => 9,
+        Error::Parquet { .. } => 10,
+    }
+}
+
+/// Parses a single ASCII character, or "tab" or "\t" for a tab.
+fn parse_char(value: &str) -> std::result::Result<u8, String> {
+    match value {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ if value.len() == 1 && value.is_ascii() => Ok(value.as_bytes()[0]),
+        _ => Err(format!("'{value}' is not a single ASCII character")),
+    }
+}
+
+fn parse_encoding(value: &str) -> std::result::Result<&'static Encoding, String> {
+    Encoding::for_label(value.as_bytes()).ok_or_else(|| format!("unknown encoding '{value}'"))
+}
+
+/// The config file, if any, with the command line flags in place of its settings.
+fn config(args: &Args) -> Result<Config> {
+    let file = match &args.config {
+        Some(path) => Config::from_file(Path::new(path))?,
+        None => Config::default(),
+    };
+    let csv = DialectConfig {
+        delimiter: args.delimiter,
+        quote: args.quote,
+        escape: args.escape,
+        has_header: args.no_header.then_some(false),
+        encoding: args.encoding,
+    };
+    let timestamp_formats = if args.timestamp_format.is_empty() {
+        file.timestamp_formats
+    } else {
+        args.timestamp_format.clone()
+    };
+
+    Ok(Config {
+        preset: args.preset.or(file.preset),
+        csv_shape: args.csv_shape.or(file.csv_shape),
+        timestamp_formats,
+        input_timezone: args.input_timezone.or(file.input_timezone),
+        output_timezone: args.output_timezone.or(file.output_timezone),
+        columns: args.columns.clone().or(file.columns),
+        attributes: file.attributes,
+        csv: csv.or(file.csv),
+    })
+}
+
+/// Saves the config with the full column mapping it resolved to, if requested.
+fn save_config(args: &Args, config: &Config, columns: Option<&EventLogColumns>) -> Result<()> {
+    if let Some(path) = &args.save_config {
+        let saved = Config {
+            timestamp_formats: config.timestamp_formats.clone(),
+            input_timezone: config.input_timezone,
+            output_timezone: config.output_timezone,
+            csv: config.csv.clone(),
+            ..columns.map(Config::from_columns).unwrap_or_default()
+        };
+        saved.save(Path::new(path))?;
+    }
+    Ok(())
+}
+
+/// Path of standard input and output.
+const STDIO: &str = "-";
+
+fn display_path(path: &Path, stdio: &str) -> String {
+    if path == Path::new(STDIO) {
+        stdio.to_string()
+    } else {
+        format!("'{}'", path.display())
+    }
+}
 
-    let mut log = lifecycle::parse_file(input_log, args.no_start_events);
-    let event_log = conversion::lifecycle_to_interval(&mut log);
+/// The format given by --from or --to, if any.
+fn named_format<'a>(registry: &'a FormatRegistry, name: Option<&str>) -> Result<Option<&'a Format>> {
+    name.map(|name| {
+        registry.by_name(name).ok_or_else(|| Error::UnsupportedFormat {
+            name: name.to_string(),
+        })
+    })
+    .transpose()
+}
 
-    let input_log_path = Path::new(&input_log);
-    let output_file_path = output_dir
-        .join(input_log_path.file_name().unwrap())
-        .with_extension("csv");
+/// The format of an input by its file extension, or else by its first bytes after decompression.
+fn input_format<'a>(registry: &'a FormatRegistry, path: &Path, open_input: &InputSource) -> Result<&'a Format> {
+    if let Some(format) = registry.by_extension(&Compression::strip_extension(path)) {
+        return Ok(format);
+    }
+    let mut start = Vec::new();
+    open_input()?.take(1024).read_to_end(&mut start)?;
+    registry.detect(&start).ok_or_else(|| Error::UnknownFormat {
+        path: display_path(path, "standard input"),
+    })
+}
+
+fn output_format<'a>(registry: &'a FormatRegistry, path: &Path) -> Result<&'a Format> {
+    registry.by_extension(&Compression::strip_extension(path)).ok_or_else(|| Error::UnknownFormat {
+        path: display_path(path, "standard output"),
+    })
+}
 
-    let mut csv_file = std::fs::File::create(output_file_path).unwrap();
-    io::interval_to_csv(&event_log, &mut csv_file);
+/// Standard input after decompression, with its start read ahead to detect the format.
+struct Stdin {
+    start: Vec<u8>,
+    rest: Box<dyn Read>,
 }
 
-pub fn convert_csv_to_xes(args: &Args) {
-    let input_log = Path::new(&args.input_log);
-    let output_dir = Path::new(&args.output_dir);
+impl Stdin {
+    fn read_start() -> io::Result<Stdin> {
+        let mut rest = compression::decompress(io::stdin().lock())?;
+        let mut start = Vec::new();
+        rest.by_ref().take(1024).read_to_end(&mut start)?;
+        Ok(Stdin { start, rest })
+    }
 
-    let mut event_log = EventLog {
-        traces: Vec::new(),
-        columns: EventLogColumns {
-            case: args.case.clone(),
-            activity: args.activity.clone(),
-            resource: args.resource.clone(),
-            start_time: args.start_time.clone(),
-            end_time: args.end_time.clone(),
-            variant: args.variant.clone(),
+    fn into_reader(self) -> Box<dyn Read> {
+        Box::new(io::Cursor::new(self.start).chain(self.rest))
+    }
+}
+
+/// Standard input for the reader: streamed as it comes if the reader and writer make a single pass over it, or
+/// copied to a temporary file if they need several.
+enum StdinSource {
+    Streamed(RefCell<Option<Box<dyn Read>>>),
+    Spooled(File),
+}
+
+impl StdinSource {
+    fn new(stdin: Stdin, several_passes: bool) -> io::Result<StdinSource> {
+        if !several_passes {
+            return Ok(StdinSource::Streamed(RefCell::new(Some(stdin.into_reader()))));
+        }
+        let mut file = tempfile::tempfile()?;
+        io::copy(&mut stdin.into_reader(), &mut file)?;
+        Ok(StdinSource::Spooled(file))
+    }
+
+    fn open(&self) -> io::Result<Box<dyn Read>> {
+        match self {
+            StdinSource::Streamed(reader) => {
+                reader.take().ok_or_else(|| io::Error::other("standard input was already read"))
+            }
+            StdinSource::Spooled(file) => {
+                let mut file = file.try_clone()?;
+                file.rewind()?;
+                Ok(Box::new(file))
+            }
+        }
+    }
+}
+
+/// Converts the input log to the output path. xes-to-csv and csv-to-xes fix the input format and name the output
+/// after the input in the output directory.
+pub fn run(args: &Args) -> Result<()> {
+    let config = config(args)?;
+    let conversion = ConversionOptions {
+        pair_by: args.pair_by,
+        unpaired: args.unpaired,
+    };
+    let parquet = ParquetFormat::new(config.clone(), conversion.clone())
+        .with_row_group_size(args.row_group_size)
+        .with_notify(|note| eprintln!("{note}"));
+    let csv = Rc::new(CsvFormat::new(config.clone(), conversion).with_notify(|note| eprintln!("{note}")));
+    let xes = XesFormat {
+        filter_start_end_events: args.no_start_events,
+        timestamps: config.timestamp_parser(),
+        indent: args.indent,
+    };
+    let mxml = MxmlFormat {
+        filter_start_end_events: xes.filter_start_end_events,
+        timestamps: xes.timestamps.clone(),
+        indent: args.indent,
+    };
+    let ocel = OcelFormat {
+        syntax: OcelSyntax::Json,
+        mapping: ObjectMapping {
+            case_type: args.object_type.clone(),
+            resource_type: args.resource_type.clone(),
         },
+        timestamps: xes.timestamps.clone(),
+        indent: args.indent,
     };
-    let bytes = std::fs::read(input_log).unwrap();
-    io::csv_to_interval(&bytes, &mut event_log);
+    let mut registry = FormatRegistry::default();
+    registry.register(csv.clone().shared_format());
+    registry.register(xes.format());
+    registry.register(mxml.format());
+    registry.register(ocel.clone().format());
+    registry.register(
+        OcelFormat {
+            syntax: OcelSyntax::Xml,
+            ..ocel
+        }
+        .format(),
+    );
+    registry.register(parquet.format());
+
+    let (input, output, from, to) = match &args.action {
+        Action::Convert { input, output } => (Path::new(input), PathBuf::from(output), None, None),
+        Action::XesToCsv | Action::CsvToXes => {
+            let (from, to) = match args.action {
+                Action::XesToCsv => ("xes", "csv"),
+                _ => ("csv", "xes"),
+            };
+            let input = Path::new(args.input_log.as_deref().unwrap_or_default());
+            let output = match args.output_dir.as_str() {
+                STDIO => PathBuf::from(STDIO),
+                output_dir => {
+                    let file_name = input.file_name().ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("'{}' has no file name to name the output after", input.display()),
+                        )
+                    })?;
+                    let file_name = Compression::strip_extension(Path::new(file_name));
+                    Path::new(output_dir).join(file_name).with_extension(to)
+                }
+            };
+            (input, output, registry.by_name(from), registry.by_name(to))
+        }
+    };
+
+    let stdin = (input == Path::new(STDIO)).then(Stdin::read_start).transpose()?;
+    let from = match named_format(&registry, args.from.as_deref())?.or(from) {
+        Some(from) => from,
+        None => {
+            let open_start = || -> io::Result<Box<dyn Read>> {
+                match &stdin {
+                    Some(stdin) => Ok(Box::new(io::Cursor::new(stdin.start.clone()))),
+                    None => compression::decompress(File::open(input)?),
+                }
+            };
+            input_format(&registry, input, &open_start)?
+        }
+    };
+    let to = match named_format(&registry, args.to.as_deref())?.or(to) {
+        Some(to) => to,
+        None => output_format(&registry, &output)?,
+    };
+
+    // Standard input is only copied to a temporary file if the reader or the writer reads it more than once
+    let several_passes = from.reader.needs_several_passes() || to.writer.needs_several_passes();
+    let stdin = stdin.map(|stdin| StdinSource::new(stdin, several_passes)).transpose()?;
+    let open_input = || -> io::Result<Box<dyn Read>> {
+        match &stdin {
+            Some(stdin) => stdin.open(),
+            None => compression::decompress(File::open(input)?),
+        }
+    };
+
+    let read_traces = || {
+        let (header, traces) = from.reader.read(&open_input)?;
+        Ok((header, if args.compute_variants { format::number_variants(traces) } else { traces }))
+    };
+    let compression = args.compress.unwrap_or_else(|| Compression::of_path(&output));
+
+    // The log is written next to the output and renamed on success, so an invalid input leaves the output as it was
+    let temp_output = (output != Path::new(STDIO)).then(|| temp_file_for(&output)).transpose()?;
+    let sink: Box<dyn Write> = match &temp_output {
+        Some(file) => Box::new(file.as_file()),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut sink = BufWriter::new(compression.encoder(BufWriter::new(sink))?);
+    to.writer.write(&read_traces, &mut sink)?;
+    sink.into_inner().map_err(io::IntoInnerError::into_error)?.finish()?.flush()?;
+    if let Some(file) = temp_output {
+        file.persist(&output).map_err(|e| e.error)?;
+    }
+
+    save_config(args, &config, csv.input_columns().or(csv.output_columns()))
+}
+
+/// Creates a temporary file in the directory of `output`, which can be renamed to it. It gets the permissions a new
+/// output file would get.
+fn temp_file_for(output: &Path) -> io::Result<tempfile::NamedTempFile> {
+    let dir = output.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(".event_log_converter");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(std::fs::Permissions::from_mode(0o666));
+    }
+    builder.tempfile_in(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_format_inference() {
+        let registry = FormatRegistry::builtin();
+        let no_input = || -> io::Result<Box<dyn Read>> { unreachable!() };
+        assert_eq!(input_format(&registry, Path::new("log.XES"), &no_input).unwrap().name, "xes");
+        assert_eq!(output_format(&registry, Path::new("out/log.csv")).unwrap().name, "csv");
+        assert!(matches!(output_format(&registry, Path::new("log.txt")), Err(Error::UnknownFormat { .. })));
+
+        let mut xes_file = tempfile::NamedTempFile::new().unwrap();
+        xes_file.write_all(b"\xef\xbb\xbf\n  <?xml version=\"1.0\"?><log/>").unwrap();
+        let mut csv_file = tempfile::NamedTempFile::new().unwrap();
+        csv_file.write_all(b"case,activity\n").unwrap();
+
+        let open = |file: &tempfile::NamedTempFile| {
+            let path = file.path().to_path_buf();
+            move || -> io::Result<Box<dyn Read>> { Ok(Box::new(File::open(&path)?)) }
+        };
+        assert_eq!(input_format(&registry, xes_file.path(), &open(&xes_file)).unwrap().name, "xes");
+        assert_eq!(input_format(&registry, Path::new(STDIO), &open(&csv_file)).unwrap().name, "csv");
+        assert!(named_format(&registry, Some("xes")).unwrap().is_some());
+        assert!(matches!(named_format(&registry, Some("pdf")), Err(Error::UnsupportedFormat { .. })));
+    }
+
+    #[test]
+    fn test_options_after_subcommand() {
+        let args = Args::try_parse_from(["event_log_converter", "convert", "in.xes", "-", "--to", "csv", "-c", "Case"])
+            .unwrap();
+        assert_eq!(args.to.as_deref(), Some("csv"));
+        assert_eq!(args.columns.case.as_deref(), Some("Case"));
+    }
+
+    #[test]
+    fn test_run_keeps_xes_header() {
+        let mut input = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        input.push("event_logs");
+        input.push("Production.xes");
+        let output_dir = tempfile::tempdir().unwrap();
+        let output = output_dir.path().join("Production.xes");
+
+        let args = Args::parse_from([
+            "event_log_converter",
+            "convert",
+            input.to_str().unwrap(),
+            output.to_str().unwrap(),
+        ]);
+        run(&args).unwrap();
+
+        let original = crate::xes::lifecycle::parse_file(&input, false).unwrap().header;
+        let header = crate::xes::lifecycle::parse_file(&output, false).unwrap().header;
+        assert!(!original.classifiers.is_empty());
+        assert_eq!(header.classifiers, original.classifiers);
+        assert!(!original.event_globals.is_empty());
+        assert_eq!(header.trace_globals, original.trace_globals);
+        assert_eq!(header.event_globals, original.event_globals);
+        assert_eq!(header.extensions, original.extensions);
+    }
+
+    #[test]
+    fn test_run_keeps_output_on_invalid_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("invalid.xes");
+        let xes = r#"<log><trace><event><date key="time:timestamp" value="noon"/></event></trace></log>"#;
+        std::fs::write(&input, xes).unwrap();
+        let output = dir.path().join("output.csv");
+        std::fs::write(&output, "previous").unwrap();
+
+        let args = Args::parse_from([
+            "event_log_converter",
+            "convert",
+            input.to_str().unwrap(),
+            output.to_str().unwrap(),
+        ]);
+        assert!(run(&args).is_err());
+
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "previous");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_run_input_without_file_name() {
+        let output_dir = tempfile::tempdir().unwrap();
+        let args = Args::parse_from([
+            "event_log_converter",
+            "-i",
+            "..",
+            "-o",
+            output_dir.path().to_str().unwrap(),
+            "xes-to-csv",
+        ]);
+
+        let error = run(&args).unwrap_err();
+        assert!(matches!(&error, Error::Io(e) if e.kind() == io::ErrorKind::InvalidInput));
+        assert!(error.to_string().contains("'..'"));
+    }
 
-    let interval_log = conversion::interval_to_lifecycle(&event_log);
+    #[test]
+    fn test_stdin_source() {
+        let stdin = || Stdin {
+            start: b"case,".to_vec(),
+            rest: Box::new(io::Cursor::new(b"activity\n1,A\n".to_vec())),
+        };
+        let read = |source: &StdinSource| {
+            let mut data = String::new();
+            source.open()?.read_to_string(&mut data)?;
+            io::Result::Ok(data)
+        };
 
-    let output_file_path = output_dir.join(input_log.file_name().unwrap()).with_extension("xes");
+        let streamed = StdinSource::new(stdin(), false).unwrap();
+        assert_eq!(read(&streamed).unwrap(), "case,activity\n1,A\n");
+        assert!(read(&streamed).is_err());
 
-    let mut xes_file = BufWriter::new(std::fs::File::create(output_file_path).unwrap());
-    io::lifecycle_to_xes(&interval_log, &mut xes_file);
+        let spooled = StdinSource::new(stdin(), true).unwrap();
+        assert_eq!(read(&spooled).unwrap(), "case,activity\n1,A\n");
+        assert_eq!(read(&spooled).unwrap(), "case,activity\n1,A\n");
+    }
 }
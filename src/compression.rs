@@ -0,0 +1,135 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression of an event log file. Applies to any format, e.g., `.xes.gz` or `.csv.zst`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Compression {
+    #[default]
+    None,
+
+    /// gzip, with the `.gz` extension
+    Gzip,
+
+    /// Zstandard, with the `.zst` extension
+    Zstd,
+}
+
+impl Compression {
+    /// The compression of a file by its extension.
+    pub fn of_path(path: &Path) -> Compression {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("gz") => Compression::Gzip,
+            Some(extension) if extension.eq_ignore_ascii_case("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// The compression of a file that starts with these bytes.
+    pub fn detect(start: &[u8]) -> Compression {
+        if start.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if start.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+
+    /// The path without the compression extension, which then ends with the extension of the event log format.
+    pub fn strip_extension(path: &Path) -> PathBuf {
+        match Compression::of_path(path) {
+            Compression::None => path.to_path_buf(),
+            _ => path.with_extension(""),
+        }
+    }
+
+    /// Compresses everything written to `writer` until [`Encoder::finish`].
+    pub fn encoder<W: Write>(self, writer: W) -> io::Result<Encoder<W>> {
+        Ok(match self {
+            Compression::None => Encoder::None(writer),
+            Compression::Gzip => Encoder::Gzip(GzEncoder::new(writer, flate2::Compression::default())),
+            Compression::Zstd => Encoder::Zstd(zstd::Encoder::new(writer, zstd::DEFAULT_COMPRESSION_LEVEL)?),
+        })
+    }
+}
+
+/// Decompresses gzip and Zstandard input, recognized by its magic bytes, and passes other input through as it is.
+pub fn decompress(reader: impl Read + 'static) -> io::Result<Box<dyn Read>> {
+    let mut reader = BufReader::new(reader);
+    Ok(match Compression::detect(reader.fill_buf()?) {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(MultiGzDecoder::new(reader)),
+        Compression::Zstd => Box::new(zstd::Decoder::with_buffer(reader)?),
+    })
+}
+
+/// A writer that compresses its output.
+pub enum Encoder<W: Write> {
+    None(W),
+    Gzip(GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// Writes the end of the compressed stream and returns the underlying writer.
+    pub fn finish(self) -> io::Result<W> {
+        match self {
+            Encoder::None(writer) => Ok(writer),
+            Encoder::Gzip(encoder) => encoder.finish(),
+            Encoder::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Encoder::None(writer) => writer.write(buf),
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            Encoder::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Encoder::None(writer) => writer.flush(),
+            Encoder::Gzip(encoder) => encoder.flush(),
+            Encoder::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression_round_trip() {
+        let data = b"<log><trace/></log>".repeat(100);
+        for compression in [Compression::None, Compression::Gzip, Compression::Zstd] {
+            let mut encoder = compression.encoder(Vec::new()).unwrap();
+            encoder.write_all(&data).unwrap();
+            let compressed = encoder.finish().unwrap();
+            assert_eq!(Compression::detect(&compressed), compression);
+
+            let mut decompressed = Vec::new();
+            decompress(io::Cursor::new(compressed)).unwrap().read_to_end(&mut decompressed).unwrap();
+            assert_eq!(decompressed, data);
+        }
+    }
+
+    #[test]
+    fn test_compression_extensions() {
+        assert_eq!(Compression::of_path(Path::new("BPI_2012.xes.GZ")), Compression::Gzip);
+        assert_eq!(Compression::of_path(Path::new("log.csv.zst")), Compression::Zstd);
+        assert_eq!(Compression::of_path(Path::new("log.xes")), Compression::None);
+        assert_eq!(Compression::strip_extension(Path::new("dir/log.xes.gz")), Path::new("dir/log.xes"));
+        assert_eq!(Compression::strip_extension(Path::new("log.xes")), Path::new("log.xes"));
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use chrono_tz::Tz;
+use encoding_rs::Encoding;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::io::{ColumnPreset, CsvDialect, CsvShape, EventLogColumns};
+use crate::timestamp::{TimestampFormat, TimestampParser};
+
+/// Column mapping, timestamp formats and CSV dialect of a dataset, read from a TOML, YAML or JSON file so they need
+/// not be passed as flags on every run. Everything is optional, and command line flags take precedence.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preset: Option<ColumnPreset>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub csv_shape: Option<CsvShape>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timestamp_formats: Vec<TimestampFormat>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_timezone: Option<Tz>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_timezone: Option<Tz>,
+
+    /// Column names in place of the preset ones.
+    pub columns: ColumnNames,
+
+    /// CSV columns of extra attributes by attribute key, see [`EventLogColumns::attributes`].
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+
+    pub csv: DialectConfig,
+}
+
+/// Names of the mapped CSV columns. Unset names are taken from the preset.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColumnNames {
+    /// Case ID column name [default: case]
+    #[arg(short, long, global = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub case: Option<String>,
+
+    /// Activity column name [default: activity]
+    #[arg(short, long, global = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activity: Option<String>,
+
+    /// Resource column name, or "none" if the log has no resources [default: resource]
+    #[arg(short, long, global = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+
+    /// Start timestamp column name [default: start_time]
+    #[arg(short, long, global = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<String>,
+
+    /// End timestamp column name [default: end_time]
+    #[arg(short, long, global = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<String>,
+
+    /// Timestamp column name of the lifecycle and atomic CSV shapes [default: timestamp]
+    #[arg(long, global = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+
+    /// Lifecycle transition column name of the lifecycle CSV shape [default: lifecycle]
+    #[arg(long, global = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lifecycle: Option<String>,
+
+    /// Variant column name, or "none" if the log has no variants [default: variant]
+    #[arg(short, long, global = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+}
+
+/// CSV dialect settings. Unset settings keep the default dialect.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DialectConfig {
+    #[serde(with = "ascii_char", skip_serializing_if = "Option::is_none")]
+    pub delimiter: Option<u8>,
+
+    #[serde(with = "ascii_char", skip_serializing_if = "Option::is_none")]
+    pub quote: Option<u8>,
+
+    #[serde(with = "ascii_char", skip_serializing_if = "Option::is_none")]
+    pub escape: Option<u8>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_header: Option<bool>,
+
+    #[serde(with = "encoding_label", skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<&'static Encoding>,
+}
+
+/// File formats of a config, by file extension.
+enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    fn of(path: &Path) -> Result<ConfigFormat> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Ok(ConfigFormat::Toml),
+            Some("yaml" | "yml") => Ok(ConfigFormat::Yaml),
+            Some("json") => Ok(ConfigFormat::Json),
+            _ => Err(config_error(path, "expected a .toml, .yaml, .yml or .json file")),
+        }
+    }
+}
+
+fn config_error(path: &Path, message: impl ToString) -> Error {
+    Error::Config {
+        path: path.display().to_string(),
+        message: message.to_string(),
+    }
+}
+
+impl Config {
+    /// Reads a config in the format of its file extension.
+    pub fn from_file(path: &Path) -> Result<Config> {
+        let format = ConfigFormat::of(path)?;
+        let text = fs::read_to_string(path)?;
+        match format {
+            ConfigFormat::Toml => toml::from_str(&text).map_err(|e| config_error(path, e.message())),
+            ConfigFormat::Yaml => serde_yaml::from_str(&text).map_err(|e| config_error(path, e)),
+            ConfigFormat::Json => serde_json::from_str(&text).map_err(|e| config_error(path, e)),
+        }
+    }
+
+    /// Writes the config in the format of the file extension.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = match ConfigFormat::of(path)? {
+            ConfigFormat::Toml => toml::to_string(self).map_err(|e| config_error(path, e))?,
+            ConfigFormat::Yaml => serde_yaml::to_string(self).map_err(|e| config_error(path, e))?,
+            ConfigFormat::Json => serde_json::to_string_pretty(self).map_err(|e| config_error(path, e))? + "\n",
+        };
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// The preset detected from a CSV header, if neither a preset nor a column name is configured.
+    pub fn detect_preset(&self, headers: &csv::StringRecord) -> Option<ColumnPreset> {
+        if self.preset.is_some() || !self.columns.is_empty() {
+            return None;
+        }
+        ColumnPreset::detect(headers)
+    }
+
+    /// The column mapping of the preset with the configured column names in place of the preset ones. Reading a
+    /// CSV log, the mapping is fitted to its header if there is a preset, which is detected from the header if
+    /// neither a preset nor a column name is configured.
+    pub fn event_log_columns(&self, headers: Option<&csv::StringRecord>) -> EventLogColumns {
+        let preset = self
+            .preset
+            .or_else(|| headers.and_then(|headers| self.detect_preset(headers)));
+        let mut columns = preset.unwrap_or_default().columns();
+        if let Some(headers) = headers.filter(|_| preset.is_some()) {
+            columns.fit_to_headers(headers);
+        }
+        self.columns.apply(&mut columns);
+        if let Some(shape) = self.csv_shape {
+            columns.shape = shape;
+        }
+        columns.attributes = self.attributes.clone();
+        columns
+    }
+
+    /// The configured timestamp formats and timezones, with the defaults for those not set.
+    pub fn timestamp_parser(&self) -> TimestampParser {
+        let default = TimestampParser::default();
+        TimestampParser {
+            formats: if self.timestamp_formats.is_empty() {
+                default.formats
+            } else {
+                self.timestamp_formats.clone()
+            },
+            input_timezone: self.input_timezone.unwrap_or(default.input_timezone),
+            output_timezone: self.output_timezone,
+        }
+    }
+
+    /// A config with every column of the mapping, so the mapping can be saved and read back as it is.
+    pub fn from_columns(columns: &EventLogColumns) -> Config {
+        Config {
+            csv_shape: Some(columns.shape),
+            columns: ColumnNames::from(columns),
+            attributes: columns.attributes.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl ColumnNames {
+    pub fn is_empty(&self) -> bool {
+        *self == ColumnNames::default()
+    }
+
+    /// These names, with the names of `other` for those not set.
+    pub fn or(self, other: ColumnNames) -> ColumnNames {
+        ColumnNames {
+            case: self.case.or(other.case),
+            activity: self.activity.or(other.activity),
+            resource: self.resource.or(other.resource),
+            start_time: self.start_time.or(other.start_time),
+            end_time: self.end_time.or(other.end_time),
+            timestamp: self.timestamp.or(other.timestamp),
+            lifecycle: self.lifecycle.or(other.lifecycle),
+            variant: self.variant.or(other.variant),
+        }
+    }
+
+    /// Replaces the columns of the mapping with the names that are set. A resource or variant name of "none" removes
+    /// the column.
+    pub fn apply(&self, columns: &mut EventLogColumns) {
+        let optional_column = |name: &String| (name != "none").then(|| name.clone());
+        let names = [
+            (&self.case, &mut columns.case),
+            (&self.activity, &mut columns.activity),
+            (&self.start_time, &mut columns.start_time),
+            (&self.end_time, &mut columns.end_time),
+            (&self.timestamp, &mut columns.timestamp),
+            (&self.lifecycle, &mut columns.lifecycle),
+        ];
+        for (name, column) in names {
+            if let Some(name) = name {
+                column.clone_from(name);
+            }
+        }
+        if let Some(resource) = &self.resource {
+            columns.resource = optional_column(resource);
+        }
+        if let Some(variant) = &self.variant {
+            columns.variant = optional_column(variant);
+        }
+    }
+}
+
+impl From<&EventLogColumns> for ColumnNames {
+    fn from(columns: &EventLogColumns) -> ColumnNames {
+        let optional_column = |column: &Option<String>| Some(column.clone().unwrap_or_else(|| "none".to_string()));
+        ColumnNames {
+            case: Some(columns.case.clone()),
+            activity: Some(columns.activity.clone()),
+            resource: optional_column(&columns.resource),
+            start_time: Some(columns.start_time.clone()),
+            end_time: Some(columns.end_time.clone()),
+            timestamp: Some(columns.timestamp.clone()),
+            lifecycle: Some(columns.lifecycle.clone()),
+            variant: optional_column(&columns.variant),
+        }
+    }
+}
+
+impl DialectConfig {
+    /// These settings, with the settings of `other` for those not set.
+    pub fn or(self, other: DialectConfig) -> DialectConfig {
+        DialectConfig {
+            delimiter: self.delimiter.or(other.delimiter),
+            quote: self.quote.or(other.quote),
+            escape: self.escape.or(other.escape),
+            has_header: self.has_header.or(other.has_header),
+            encoding: self.encoding.or(other.encoding),
+        }
+    }
+
+    /// The default dialect with the settings that are set.
+    pub fn dialect(&self) -> CsvDialect {
+        let default = CsvDialect::default();
+        CsvDialect {
+            delimiter: self.delimiter.unwrap_or(default.delimiter),
+            quote: self.quote.unwrap_or(default.quote),
+            escape: self.escape.or(default.escape),
+            has_header: self.has_header.unwrap_or(default.has_header),
+            encoding: self.encoding.or(default.encoding),
+        }
+    }
+}
+
+/// A single ASCII character, serialized as a string.
+mod ascii_char {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Option<u8>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(byte) => serializer.serialize_char(char::from(*byte)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u8>, D::Error> {
+        let value = char::deserialize(deserializer)?;
+        u8::try_from(value)
+            .ok()
+            .filter(u8::is_ascii)
+            .map(Some)
+            .ok_or_else(|| de::Error::custom(format!("'{value}' is not an ASCII character")))
+    }
+}
+
+/// An encoding, serialized as its name and deserialized from any of its labels.
+mod encoding_label {
+    use encoding_rs::Encoding;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Option<&'static Encoding>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(encoding) => serializer.serialize_str(encoding.name()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<&'static Encoding>, D::Error> {
+        let label = String::deserialize(deserializer)?;
+        Encoding::for_label(label.as_bytes())
+            .map(Some)
+            .ok_or_else(|| de::Error::custom(format!("unknown encoding '{label}'")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_formats() {
+        let toml = r#"
+            preset = "disco"
+            timestamp_formats = ["%d.%m.%Y %H:%M", "iso8601"]
+            input_timezone = "Europe/Berlin"
+
+            [columns]
+            case = "Case"
+            variant = "none"
+
+            [attributes]
+            "cost:total" = "Cost"
+
+            [csv]
+            delimiter = ";"
+            encoding = "latin1"
+        "#;
+        let json = r#"{
+            "preset": "disco",
+            "timestamp_formats": ["%d.%m.%Y %H:%M", "iso8601"],
+            "input_timezone": "Europe/Berlin",
+            "columns": { "case": "Case", "variant": "none" },
+            "attributes": { "cost:total": "Cost" },
+            "csv": { "delimiter": ";", "encoding": "latin1" }
+        }"#;
+
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(serde_json::from_str::<Config>(json).unwrap(), config);
+        assert_eq!(
+            serde_yaml::from_str::<Config>(&serde_yaml::to_string(&config).unwrap()).unwrap(),
+            config
+        );
+
+        let mut columns = config.preset.unwrap().columns();
+        config.columns.apply(&mut columns);
+        assert_eq!(columns.case, "Case");
+        assert_eq!(columns.activity, "Activity");
+        assert_eq!(columns.variant, None);
+        assert_eq!(
+            config.timestamp_formats[0],
+            TimestampFormat::Custom("%d.%m.%Y %H:%M".to_string())
+        );
+        assert_eq!(config.csv.dialect().delimiter, b';');
+        assert_eq!(config.csv.dialect().encoding, Some(encoding_rs::WINDOWS_1252));
+
+        assert!(toml::from_str::<Config>("[csv]\ndelimiter = \"§\"").is_err());
+        assert!(toml::from_str::<Config>("[columns]\nactivty = \"a\"").is_err());
+    }
+
+    #[test]
+    fn test_config_from_columns() {
+        let mut columns = ColumnPreset::Pm4py.columns();
+        columns.shape = CsvShape::Atomic;
+        columns.attributes.insert("cost:total".to_string(), "Cost".to_string());
+        let config = Config::from_columns(&columns);
+
+        let saved = toml::to_string(&config).unwrap();
+        let config: Config = toml::from_str(&saved).unwrap();
+        let mut read_columns = EventLogColumns::default_style();
+        config.columns.apply(&mut read_columns);
+        read_columns.shape = config.csv_shape.unwrap();
+        read_columns.attributes = config.attributes;
+
+        assert_eq!(
+            serde_json::to_value(read_columns).unwrap(),
+            serde_json::to_value(columns).unwrap()
+        );
+    }
+}
@@ -1,93 +1,412 @@
+use std::collections::HashMap;
+
+use crate::error::{Error, Result};
 use crate::io;
-use crate::xes::interval::{Event, EventLog, Trace};
+use crate::timestamp::Timestamp;
+use crate::xes::interval::{Completion, Event, EventLog, Suspension, Trace};
 use crate::xes::lifecycle;
 
-pub fn lifecycle_to_interval(lifecycle_log: &mut lifecycle::EventLog) -> EventLog {
+/// Options for converting lifecycle logs into interval logs.
+#[derive(Debug, Clone, Default)]
+pub struct ConversionOptions {
+    pub pair_by: PairBy,
+    pub unpaired: UnpairedPolicy,
+}
+
+/// What a `complete` event must share with a `start` event, besides the activity, to close its interval.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PairBy {
+    #[default]
+    Activity,
+    Resource,
+
+    // The concept:instance attribute, which identifies the activity instance in the XES Concept extension
+    Instance,
+}
+
+/// How to convert a `start` without a `complete`, or a `complete` without a `start`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum UnpairedPolicy {
+    /// Fail with Error::UnpairedEvents
+    #[default]
+    Error,
+
+    /// Make an interval that starts and ends at the event's timestamp
+    Instant,
+
+    /// Leave the event out. Events with an unknown transition are always left out unless the policy is `error`
+    Drop,
+
+    /// Take the missing timestamp from the chronologically adjacent event of the trace
+    Estimate,
+}
+
+/// Number of events of a trace that had no counterpart and were handled by the unpaired policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnpairedSummary {
+    pub starts: usize,
+    pub completes: usize,
+
+    /// Events with a transition that is not a standard lifecycle transition, which cannot be paired either.
+    pub unknown: usize,
+}
+
+impl UnpairedSummary {
+    pub fn is_empty(&self) -> bool {
+        self.starts == 0 && self.completes == 0 && self.unknown == 0
+    }
+}
+
+/// Numbers the distinct activity sequences of traces in order of first appearance, for logs without variants.
+#[derive(Debug, Clone, Default)]
+pub struct VariantNumbering {
+    variants: HashMap<Vec<String>, usize>,
+}
+
+impl VariantNumbering {
+    /// Sets the variant of a trace that has none to the number of its activity sequence.
+    pub fn assign(&mut self, trace: &mut Trace) {
+        if trace.variant.is_empty() {
+            trace.variant = self.number(trace.events.iter().map(|event| &event.activity));
+        }
+    }
+
+    /// Like [`VariantNumbering::assign`] for a lifecycle trace, whose activity sequence is that of its `complete`
+    /// events and events without a transition.
+    pub fn assign_lifecycle(&mut self, trace: &mut lifecycle::Trace) {
+        if trace.variant.is_empty() {
+            let completed = trace
+                .events
+                .iter()
+                .filter(|event| event.is_complete());
+            trace.variant = self.number(completed.map(|event| &event.activity));
+        }
+    }
+
+    fn number<'a>(&mut self, activities: impl Iterator<Item = &'a String>) -> String {
+        let next = self.variants.len() + 1;
+        self.variants
+            .entry(activities.cloned().collect())
+            .or_insert(next)
+            .to_string()
+    }
+}
+
+pub fn lifecycle_to_interval(lifecycle_log: &lifecycle::EventLog, options: &ConversionOptions) -> Result<EventLog> {
     let mut event_log = EventLog {
         traces: vec![],
-        columns: io::EventLogColumns::default_style()
+        columns: io::EventLogColumns::default_style(),
     };
 
-    for trace in &mut lifecycle_log.traces {
-        let mut new_trace = Trace {
-            case: trace.case.clone(),
-            variant: trace.variant.clone(),
-            events: Vec::new(),
-        };
-
-        let events = &mut trace.events;
-        events.sort_unstable_by_key(|e| e.activity.clone());
-
-        for same_activities in events.group_by(|a, b| a.activity == b.activity) {
-            assert_eq!(
-                same_activities.len() % 2,
-                0,
-                "Lifecycle log is not valid. Group {same_activities:?}"
-            );
-
-            for pair in same_activities.chunks_exact(2) {
-                let mut new_event = Event {
-                    activity: pair[0].activity.clone(),
-                    resource: pair[0].resource.clone(),
-                    start_time: pair[0].timestamp.clone(),
-                    end_time: pair[0].timestamp.clone(),
+    for trace in &lifecycle_log.traces {
+        event_log.traces.push(lifecycle_trace_to_interval(trace, options)?);
+    }
+
+    Ok(event_log)
+}
+
+/// Converts a single lifecycle trace into an interval trace. Used by the streaming conversion, which never
+/// holds more than one trace in memory.
+///
+/// Events are walked in timestamp order and each `complete` closes the earliest open `start` of the same
+/// activity, so repeated and concurrent executions are paired correctly. Intervals are ordered by their start.
+pub fn lifecycle_trace_to_interval(trace: &lifecycle::Trace, options: &ConversionOptions) -> Result<Trace> {
+    lifecycle_trace_to_interval_with_summary(trace, options).map(|(trace, _)| trace)
+}
+
+/// Same as [`lifecycle_trace_to_interval`], but also reports how many events the unpaired policy handled.
+pub fn lifecycle_trace_to_interval_with_summary(
+    trace: &lifecycle::Trace,
+    options: &ConversionOptions,
+) -> Result<(Trace, UnpairedSummary)> {
+    let mut new_trace = Trace {
+        case: trace.case.clone(),
+        variant: trace.variant.clone(),
+        events: Vec::new(),
+        attributes: trace.attributes.clone(),
+    };
+    let mut summary = UnpairedSummary::default();
+
+    let mut events = trace
+        .events
+        .iter()
+        .map(|event| match event.timestamp {
+            Some(timestamp) => Ok((event, timestamp)),
+            None => Err(Error::InvalidTimestamp {
+                value: None,
+                location: format!("case '{}', event '{}'", trace.case, event.activity),
+            }),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    events.sort_by_key(|(_, timestamp)| *timestamp);
+
+    // Times of `schedule` and `assign` transitions not followed by a start yet
+    let mut scheduled: Vec<(PairKey, Timestamp)> = Vec::new();
+    let mut open: Vec<OpenInterval> = Vec::new();
+
+    for (position, &(event, timestamp)) in events.iter().enumerate() {
+        let key = PairKey::new(event, options.pair_by);
+        // Transitions are matched regardless of case, as some logs write them in capitals
+        let transition = event.lifecycle.to_ascii_lowercase();
+        match transition.as_str() {
+            "schedule" | "assign" | "reassign" if !scheduled.iter().any(|(scheduled_key, _)| *scheduled_key == key) => {
+                scheduled.push((key, timestamp));
+            }
+            "withdraw" => {
+                if let Some(i) = scheduled.iter().position(|(scheduled_key, _)| *scheduled_key == key) {
+                    scheduled.remove(i);
+                }
+            }
+            "start" => {
+                let scheduled_time = scheduled
+                    .iter()
+                    .position(|(scheduled_key, _)| *scheduled_key == key)
+                    .map(|i| scheduled.remove(i).1);
+                open.push(OpenInterval {
+                    key,
+                    index: new_trace.events.len(),
+                    position,
+                    suspend_time: None,
+                });
+                new_trace.events.push(Event {
+                    activity: event.activity.clone(),
+                    resource: event.resource.clone(),
+                    start_time: timestamp,
+                    end_time: timestamp,
+                    scheduled_time,
+                    attributes: event.attributes.clone(),
+                    ..Default::default()
+                });
+            }
+            "suspend" => {
+                if let Some(interval) = open.iter_mut().find(|i| i.key == key && i.suspend_time.is_none()) {
+                    interval.suspend_time = Some(timestamp);
+                }
+            }
+            "resume" => {
+                if let Some(interval) = open.iter_mut().find(|i| i.key == key && i.suspend_time.is_some()) {
+                    new_trace.events[interval.index].suspensions.push(Suspension {
+                        suspend_time: interval.suspend_time.take().unwrap(),
+                        resume_time: timestamp,
+                    });
+                }
+            }
+            // Events without a transition are taken as complete, as in logs without the Lifecycle extension
+            "complete" | "ate_abort" | "" => {
+                let completion = if transition == "ate_abort" {
+                    Completion::Aborted
+                } else {
+                    Completion::Complete
                 };
 
-                for event in pair {
-                    if event.lifecycle == "start" {
-                        new_event.start_time = event.timestamp.clone();
-                    } else if event.lifecycle == "complete" {
-                        new_event.end_time = event.timestamp.clone();
+                let Some(open_position) = open.iter().position(|i| i.key == key) else {
+                    // An activity instance aborted before it started leaves no interval
+                    if completion == Completion::Aborted {
+                        continue;
                     }
-                }
 
-                new_trace.events.push(new_event);
+                    summary.completes += 1;
+                    let start_time = match options.unpaired {
+                        UnpairedPolicy::Error => return Err(unpaired_events(trace, &event.activity, 1)),
+                        UnpairedPolicy::Drop => continue,
+                        UnpairedPolicy::Instant => timestamp,
+                        UnpairedPolicy::Estimate => match position.checked_sub(1) {
+                            Some(previous) => events[previous].1,
+                            None => timestamp,
+                        },
+                    };
+                    new_trace.events.push(Event {
+                        activity: event.activity.clone(),
+                        resource: event.resource.clone(),
+                        start_time,
+                        end_time: timestamp,
+                        attributes: event.attributes.clone(),
+                        ..Default::default()
+                    });
+                    continue;
+                };
+
+                let interval = open.remove(open_position);
+                close_interval(&mut new_trace.events[interval.index], &interval, event, timestamp, completion);
+            }
+            "pi_abort" => {
+                for interval in open.drain(..) {
+                    close_interval(
+                        &mut new_trace.events[interval.index],
+                        &interval,
+                        event,
+                        timestamp,
+                        Completion::ProcessAborted,
+                    );
+                }
+                scheduled.clear();
+            }
+            // A repeated schedule keeps the first, and skipped activities were not executed
+            "schedule" | "assign" | "reassign" | "autoskip" | "manualskip" => (),
+            _ => {
+                summary.unknown += 1;
+                if options.unpaired == UnpairedPolicy::Error {
+                    return Err(unpaired_events(trace, &event.activity, 1));
+                }
             }
         }
+    }
 
-        event_log.traces.push(new_trace);
+    summary.starts = open.len();
+    match options.unpaired {
+        UnpairedPolicy::Error => {
+            if let Some(interval) = open.first() {
+                let activity = interval.key.activity;
+                let events = open.iter().filter(|i| i.key.activity == activity).count();
+                return Err(unpaired_events(trace, activity, events));
+            }
+        }
+        UnpairedPolicy::Drop => {
+            let dropped = open.iter().map(|interval| interval.index).collect::<Vec<_>>();
+            new_trace.events = std::mem::take(&mut new_trace.events)
+                .into_iter()
+                .enumerate()
+                .filter(|(index, _)| !dropped.contains(index))
+                .map(|(_, event)| event)
+                .collect();
+        }
+        // The interval already ends where it starts
+        UnpairedPolicy::Instant => (),
+        UnpairedPolicy::Estimate => {
+            for interval in &open {
+                if let Some(next) = events.get(interval.position + 1) {
+                    new_trace.events[interval.index].end_time = next.1;
+                }
+            }
+        }
     }
 
-    event_log
+    Ok((new_trace, summary))
+}
+
+/// An interval that has started but not ended yet.
+struct OpenInterval<'a> {
+    key: PairKey<'a>,
+
+    /// Index of the interval in the new trace.
+    index: usize,
+
+    /// Index of the start event in the chronologically sorted events.
+    position: usize,
+
+    /// Time of the `suspend` transition while the interval is suspended.
+    suspend_time: Option<Timestamp>,
 }
 
-pub fn interval_to_lifecycle(event_log: &EventLog) -> lifecycle::EventLog {
-    let mut lifecycle_log = lifecycle::EventLog { traces: vec![] };
+fn close_interval(
+    interval: &mut Event,
+    open: &OpenInterval,
+    event: &lifecycle::Event,
+    timestamp: Timestamp,
+    completion: Completion,
+) {
+    interval.end_time = timestamp;
+    interval.completion = completion;
+    if let Some(suspend_time) = open.suspend_time {
+        interval.suspensions.push(Suspension {
+            suspend_time,
+            resume_time: timestamp,
+        });
+    }
+    if interval.resource.is_empty() {
+        interval.resource = event.resource.clone();
+    }
+    for attribute in &event.attributes {
+        interval.attributes.insert(attribute.clone());
+    }
+}
 
-    for trace in &event_log.traces {
-        let mut new_trace = lifecycle::Trace {
-            case: trace.case.clone(),
-            variant: trace.variant.clone(),
-            events: Vec::new(),
+/// Identifies which open interval a `complete` event belongs to.
+#[derive(Debug, PartialEq)]
+struct PairKey<'a> {
+    activity: &'a str,
+    discriminator: Option<String>,
+}
+
+impl<'a> PairKey<'a> {
+    fn new(event: &'a lifecycle::Event, pair_by: PairBy) -> PairKey<'a> {
+        let discriminator = match pair_by {
+            PairBy::Activity => None,
+            PairBy::Resource => Some(event.resource.clone()),
+            PairBy::Instance => event
+                .attributes
+                .get("concept:instance")
+                .and_then(|attribute| attribute.value.to_xes_value()),
         };
 
-        for event in &trace.events {
-            new_trace.events.push(lifecycle::Event {
-                activity: event.activity.clone(),
-                resource: event.resource.clone(),
-                lifecycle: "start".to_string(),
-                timestamp: event.start_time.clone(),
-            });
-            new_trace.events.push(lifecycle::Event {
-                activity: event.activity.clone(),
-                resource: event.resource.clone(),
-                lifecycle: "complete".to_string(),
-                timestamp: event.end_time.clone(),
-            });
+        PairKey {
+            activity: &event.activity,
+            discriminator,
         }
+    }
+}
 
-        lifecycle_log.traces.push(new_trace);
+/// The error for `events` lifecycle events of an activity that are left without a counterpart.
+fn unpaired_events(trace: &lifecycle::Trace, activity: &str, events: usize) -> Error {
+    Error::UnpairedEvents {
+        case: trace.case.clone(),
+        activity: activity.to_string(),
+        events,
+    }
+}
+
+pub fn interval_to_lifecycle(event_log: &EventLog) -> Result<lifecycle::EventLog> {
+    Ok(lifecycle::EventLog {
+        traces: event_log.traces.iter().map(interval_trace_to_lifecycle).collect(),
+        ..Default::default()
+    })
+}
+
+/// Emits the lifecycle transitions of every activity instance of a trace.
+pub fn interval_trace_to_lifecycle(trace: &Trace) -> lifecycle::Trace {
+    let mut new_trace = lifecycle::Trace {
+        case: trace.case.clone(),
+        variant: trace.variant.clone(),
+        events: Vec::new(),
+        attributes: trace.attributes.clone(),
+    };
+
+    for event in &trace.events {
+        let transition = |lifecycle: &str, timestamp: Timestamp| lifecycle::Event {
+            activity: event.activity.clone(),
+            resource: event.resource.clone(),
+            lifecycle: lifecycle.to_string(),
+            timestamp: Some(timestamp),
+            attributes: event.attributes.clone(),
+        };
+
+        if let Some(scheduled_time) = event.scheduled_time {
+            new_trace.events.push(transition("schedule", scheduled_time));
+        }
+        new_trace.events.push(transition("start", event.start_time));
+        for suspension in &event.suspensions {
+            new_trace.events.push(transition("suspend", suspension.suspend_time));
+            new_trace.events.push(transition("resume", suspension.resume_time));
+        }
+        new_trace
+            .events
+            .push(transition(event.completion.lifecycle_transition(), event.end_time));
     }
 
-    lifecycle_log
+    new_trace
 }
 
 #[cfg(test)]
 mod tests {
     use std::path::{Path, PathBuf};
 
-    use crate::{conversion, io};
+    use crate::conversion::{ConversionOptions, PairBy, UnpairedPolicy, UnpairedSummary, VariantNumbering};
+    use crate::error::Error;
+    use crate::timestamp::{Timestamp, TimestampFormat, TimestampParser};
+    use crate::xes::attribute::Attributes;
+    use crate::xes::interval::Completion;
     use crate::xes::{interval, lifecycle};
+    use crate::{conversion, io};
 
     fn test_log_path() -> String {
         let mut project_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -100,8 +419,8 @@ mod tests {
     fn test_event_log_to_csv() {
         let file_path = test_log_path();
 
-        let mut log = lifecycle::parse_file(&Path::new(&file_path), true);
-        let event_log = conversion::lifecycle_to_interval(&mut log);
+        let log = lifecycle::parse_file(Path::new(&file_path), true).unwrap();
+        let event_log = conversion::lifecycle_to_interval(&log, &ConversionOptions::default()).unwrap();
 
         assert_eq!(event_log.traces.len(), log.traces.len());
     }
@@ -114,11 +433,399 @@ mod tests {
             traces: Vec::new(),
             columns: io::EventLogColumns::default_style(),
         };
-        io::csv_to_interval(data.as_bytes(), &mut event_log);
+        io::csv_to_interval(data.as_bytes(), &mut event_log, &TimestampParser::new(vec![TimestampFormat::EpochSeconds]))
+            .unwrap();
 
-        let lifecycle_log = conversion::interval_to_lifecycle(&event_log);
+        let lifecycle_log = conversion::interval_to_lifecycle(&event_log).unwrap();
 
         assert_eq!(lifecycle_log.traces.len(), 1);
         assert_eq!(lifecycle_log.traces[0].events.len(), 2);
     }
+
+    #[test]
+    fn test_variant_numbering() {
+        let trace = |case: &str, variant: &str, activities: &[&str]| interval::Trace {
+            case: case.to_string(),
+            variant: variant.to_string(),
+            events: activities
+                .iter()
+                .map(|activity| interval::Event {
+                    activity: activity.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            attributes: Attributes::new(),
+        };
+        let mut traces = [
+            trace("1", "", &["A", "B"]),
+            trace("2", "", &["A", "C"]),
+            trace("3", "", &["A", "B"]),
+            trace("4", "given", &["A", "B"]),
+        ];
+
+        let mut variants = VariantNumbering::default();
+        for trace in &mut traces {
+            variants.assign(trace);
+        }
+
+        let variants = traces.iter().map(|trace| trace.variant.as_str()).collect::<Vec<_>>();
+        assert_eq!(variants, ["1", "2", "1", "given"]);
+    }
+
+    #[test]
+    fn test_unpaired_events() {
+        let trace = lifecycle::Trace {
+            case: "1".to_string(),
+            variant: "1".to_string(),
+            events: vec![lifecycle::Event {
+                activity: "A".to_string(),
+                resource: "R1".to_string(),
+                timestamp: Some(timestamp("2022-01-01T09:00:00")),
+                lifecycle: "start".to_string(),
+                attributes: Attributes::new(),
+            }],
+            attributes: Attributes::new(),
+        };
+
+        let result = conversion::lifecycle_trace_to_interval(&trace, &ConversionOptions::default());
+
+        assert!(matches!(result, Err(Error::UnpairedEvents { case, .. }) if case == "1"));
+    }
+
+    #[test]
+    fn test_unpaired_events_count() {
+        let trace = lifecycle::Trace {
+            case: "1".to_string(),
+            events: vec![
+                lifecycle_event("A", "R1", "start", "2022-01-01T09:00:00"),
+                lifecycle_event("A", "R1", "complete", "2022-01-01T09:30:00"),
+                lifecycle_event("A", "R1", "start", "2022-01-01T10:00:00"),
+                lifecycle_event("A", "R2", "start", "2022-01-01T10:05:00"),
+                lifecycle_event("A", "R1", "complete", "2022-01-01T10:30:00"),
+                lifecycle_event("A", "R1", "start", "2022-01-01T11:00:00"),
+                lifecycle_event("A", "R1", "start", "2022-01-01T11:05:00"),
+            ],
+            ..Default::default()
+        };
+
+        let result = conversion::lifecycle_trace_to_interval(&trace, &ConversionOptions::default());
+        assert!(matches!(result, Err(Error::UnpairedEvents { events: 3, .. })));
+
+        let trace = lifecycle::Trace {
+            case: "1".to_string(),
+            events: vec![
+                lifecycle_event("A", "R1", "start", "2022-01-01T09:00:00"),
+                lifecycle_event("A", "R1", "complete", "2022-01-01T09:30:00"),
+                lifecycle_event("A", "R1", "complete", "2022-01-01T10:00:00"),
+            ],
+            ..Default::default()
+        };
+
+        let result = conversion::lifecycle_trace_to_interval(&trace, &ConversionOptions::default());
+        assert!(matches!(result, Err(Error::UnpairedEvents { events: 1, .. })));
+    }
+
+    fn timestamp(value: &str) -> Timestamp {
+        TimestampFormat::Iso8601.parse(value).unwrap()
+    }
+
+    fn lifecycle_event(activity: &str, resource: &str, lifecycle: &str, at: &str) -> lifecycle::Event {
+        lifecycle::Event {
+            activity: activity.to_string(),
+            resource: resource.to_string(),
+            timestamp: Some(timestamp(at)),
+            lifecycle: lifecycle.to_string(),
+            attributes: Attributes::new(),
+        }
+    }
+
+    #[test]
+    fn test_uppercase_transitions() {
+        let mut trace = lifecycle::Trace {
+            case: "1".to_string(),
+            events: vec![
+                lifecycle_event("A", "R1", "START", "2022-01-01T09:00:00"),
+                lifecycle_event("A", "R1", "COMPLETE", "2022-01-01T09:30:00"),
+                lifecycle_event("B", "R1", "Start", "2022-01-01T10:00:00"),
+                lifecycle_event("B", "R1", "ATE_ABORT", "2022-01-01T10:15:00"),
+            ],
+            ..Default::default()
+        };
+
+        let (interval, summary) =
+            conversion::lifecycle_trace_to_interval_with_summary(&trace, &ConversionOptions::default()).unwrap();
+        assert!(summary.is_empty());
+        assert_eq!(interval.events.len(), 2);
+        assert_eq!(interval.events[0].end_time, timestamp("2022-01-01T09:30:00"));
+        assert_eq!(interval.events[1].completion, Completion::Aborted);
+
+        let mut numbering = VariantNumbering::default();
+        numbering.assign_lifecycle(&mut trace);
+        let mut only_a = lifecycle::Trace {
+            events: vec![lifecycle_event("A", "R1", "complete", "2022-01-01T09:30:00")],
+            ..Default::default()
+        };
+        numbering.assign_lifecycle(&mut only_a);
+        assert_eq!(trace.variant, only_a.variant);
+    }
+
+    #[test]
+    fn test_unknown_transitions() {
+        let trace = lifecycle::Trace {
+            case: "1".to_string(),
+            events: vec![
+                lifecycle_event("A", "R1", "start", "2022-01-01T09:00:00"),
+                lifecycle_event("A", "R1", "complete", "2022-01-01T09:30:00"),
+                lifecycle_event("B", "R1", "finished", "2022-01-01T10:00:00"),
+                lifecycle_event("C", "R1", "autoskip", "2022-01-01T10:30:00"),
+            ],
+            ..Default::default()
+        };
+
+        let result = conversion::lifecycle_trace_to_interval(&trace, &ConversionOptions::default());
+        assert!(matches!(result, Err(Error::UnpairedEvents { activity, events: 1, .. }) if activity == "B"));
+
+        let options = ConversionOptions {
+            unpaired: UnpairedPolicy::Drop,
+            ..Default::default()
+        };
+        let (interval, summary) = conversion::lifecycle_trace_to_interval_with_summary(&trace, &options).unwrap();
+        assert_eq!(summary.unknown, 1);
+        assert_eq!(interval.events.len(), 1);
+    }
+
+    #[test]
+    fn test_events_without_transition() {
+        let trace = lifecycle::Trace {
+            case: "1".to_string(),
+            events: vec![
+                lifecycle_event("A", "R1", "start", "2022-01-01T09:00:00"),
+                lifecycle_event("A", "R1", "", "2022-01-01T09:30:00"),
+                lifecycle_event("B", "R1", "", "2022-01-01T10:00:00"),
+            ],
+            ..Default::default()
+        };
+
+        let result = conversion::lifecycle_trace_to_interval(&trace, &ConversionOptions::default());
+        assert!(matches!(result, Err(Error::UnpairedEvents { activity, .. }) if activity == "B"));
+
+        let options = ConversionOptions {
+            unpaired: UnpairedPolicy::Instant,
+            ..Default::default()
+        };
+        let (trace, summary) = conversion::lifecycle_trace_to_interval_with_summary(&trace, &options).unwrap();
+        let intervals = trace
+            .events
+            .iter()
+            .map(|e| (e.activity.as_str(), e.start_time, e.end_time, e.completion))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            intervals,
+            [
+                (
+                    "A",
+                    timestamp("2022-01-01T09:00:00"),
+                    timestamp("2022-01-01T09:30:00"),
+                    Completion::Complete
+                ),
+                (
+                    "B",
+                    timestamp("2022-01-01T10:00:00"),
+                    timestamp("2022-01-01T10:00:00"),
+                    Completion::Complete
+                ),
+            ]
+        );
+        assert_eq!(summary.completes, 1);
+    }
+
+    #[test]
+    fn test_chronological_pairing() {
+        let trace = lifecycle::Trace {
+            case: "1".to_string(),
+            events: vec![
+                lifecycle_event("B", "R1", "start", "2022-01-01T10:00:00"),
+                lifecycle_event("A", "R1", "start", "2022-01-01T09:00:00"),
+                lifecycle_event("A", "R1", "complete", "2022-01-01T09:30:00"),
+                lifecycle_event("A", "R2", "start", "2022-01-01T11:00:00"),
+                lifecycle_event("B", "R1", "complete", "2022-01-01T11:30:00"),
+                lifecycle_event("A", "R2", "complete", "2022-01-01T12:00:00"),
+            ],
+            ..Default::default()
+        };
+
+        let trace = conversion::lifecycle_trace_to_interval(&trace, &ConversionOptions::default()).unwrap();
+
+        let intervals = trace
+            .events
+            .iter()
+            .map(|e| (e.activity.as_str(), e.start_time, e.end_time))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            intervals,
+            [
+                ("A", timestamp("2022-01-01T09:00:00"), timestamp("2022-01-01T09:30:00")),
+                ("B", timestamp("2022-01-01T10:00:00"), timestamp("2022-01-01T11:30:00")),
+                ("A", timestamp("2022-01-01T11:00:00"), timestamp("2022-01-01T12:00:00")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pairing_by_resource() {
+        let trace = lifecycle::Trace {
+            case: "1".to_string(),
+            events: vec![
+                lifecycle_event("A", "R1", "start", "2022-01-01T09:00:00"),
+                lifecycle_event("A", "R2", "start", "2022-01-01T09:10:00"),
+                lifecycle_event("A", "R2", "complete", "2022-01-01T09:20:00"),
+                lifecycle_event("A", "R1", "complete", "2022-01-01T09:30:00"),
+            ],
+            ..Default::default()
+        };
+
+        let by_activity = conversion::lifecycle_trace_to_interval(&trace, &ConversionOptions::default()).unwrap();
+        let options = ConversionOptions {
+            pair_by: PairBy::Resource,
+            ..Default::default()
+        };
+        let by_resource = conversion::lifecycle_trace_to_interval(&trace, &options).unwrap();
+
+        assert_eq!(by_activity.events[0].end_time, timestamp("2022-01-01T09:20:00"));
+        assert_eq!(by_resource.events[0].end_time, timestamp("2022-01-01T09:30:00"));
+        assert_eq!(by_resource.events[1].end_time, timestamp("2022-01-01T09:20:00"));
+    }
+
+    #[test]
+    fn test_unpaired_policies() {
+        let trace = lifecycle::Trace {
+            case: "1".to_string(),
+            events: vec![
+                lifecycle_event("A", "R1", "complete", "2022-01-01T09:00:00"),
+                lifecycle_event("B", "R1", "start", "2022-01-01T10:00:00"),
+                lifecycle_event("B", "R1", "complete", "2022-01-01T11:00:00"),
+                lifecycle_event("C", "R1", "start", "2022-01-01T12:00:00"),
+                lifecycle_event("D", "R1", "complete", "2022-01-01T13:00:00"),
+            ],
+            ..Default::default()
+        };
+        let convert = |unpaired| {
+            let options = ConversionOptions {
+                unpaired,
+                ..Default::default()
+            };
+            conversion::lifecycle_trace_to_interval_with_summary(&trace, &options)
+        };
+        let intervals = |trace: &interval::Trace| {
+            trace
+                .events
+                .iter()
+                .map(|e| {
+                    (
+                        e.activity.clone(),
+                        e.start_time.format("%H").to_string(),
+                        e.end_time.format("%H").to_string(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let interval =
+            |activity: &str, start: &str, end: &str| (activity.to_string(), start.to_string(), end.to_string());
+
+        assert!(
+            matches!(convert(UnpairedPolicy::Error), Err(Error::UnpairedEvents { activity, .. }) if activity == "A")
+        );
+
+        let (instant, summary) = convert(UnpairedPolicy::Instant).unwrap();
+        assert_eq!(
+            summary,
+            UnpairedSummary {
+                starts: 1,
+                completes: 2,
+                unknown: 0
+            }
+        );
+        assert_eq!(
+            intervals(&instant),
+            [
+                interval("A", "09", "09"),
+                interval("B", "10", "11"),
+                interval("C", "12", "12"),
+                interval("D", "13", "13")
+            ]
+        );
+
+        let (dropped, _) = convert(UnpairedPolicy::Drop).unwrap();
+        assert_eq!(intervals(&dropped), [interval("B", "10", "11")]);
+
+        let (estimated, _) = convert(UnpairedPolicy::Estimate).unwrap();
+        assert_eq!(
+            intervals(&estimated),
+            [
+                interval("A", "09", "09"),
+                interval("B", "10", "11"),
+                interval("C", "12", "13"),
+                interval("D", "12", "13")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_full_lifecycle_model() {
+        let trace = lifecycle::Trace {
+            case: "1".to_string(),
+            events: vec![
+                lifecycle_event("A", "R1", "schedule", "2022-01-01T08:00:00+00:00"),
+                lifecycle_event("A", "R1", "assign", "2022-01-01T08:30:00+00:00"),
+                lifecycle_event("A", "R1", "start", "2022-01-01T09:00:00+00:00"),
+                lifecycle_event("A", "R1", "suspend", "2022-01-01T09:10:00+00:00"),
+                lifecycle_event("A", "R1", "resume", "2022-01-01T09:40:00+00:00"),
+                lifecycle_event("A", "R1", "complete", "2022-01-01T10:00:00+00:00"),
+                lifecycle_event("B", "R1", "start", "2022-01-01T10:00:00+00:00"),
+                lifecycle_event("B", "R1", "ate_abort", "2022-01-01T10:05:00+00:00"),
+                lifecycle_event("C", "R1", "schedule", "2022-01-01T10:10:00+00:00"),
+                lifecycle_event("C", "R1", "withdraw", "2022-01-01T10:15:00+00:00"),
+                lifecycle_event("D", "R1", "start", "2022-01-01T10:20:00+00:00"),
+                lifecycle_event("D", "R1", "pi_abort", "2022-01-01T10:30:00+00:00"),
+            ],
+            ..Default::default()
+        };
+
+        let interval_trace = conversion::lifecycle_trace_to_interval(&trace, &ConversionOptions::default()).unwrap();
+
+        let a = &interval_trace.events[0];
+        assert_eq!(a.scheduled_time, Some(timestamp("2022-01-01T08:00:00+00:00")));
+        assert_eq!(a.suspensions.len(), 1);
+        assert_eq!(a.processing_time(), chrono::Duration::minutes(30));
+        assert_eq!(a.completion, Completion::Complete);
+        assert_eq!(interval_trace.events[1].completion, Completion::Aborted);
+        assert_eq!(interval_trace.events[2].activity, "D");
+        assert_eq!(interval_trace.events[2].completion, Completion::ProcessAborted);
+        assert_eq!(interval_trace.events.len(), 3);
+
+        let event_log = interval::EventLog {
+            traces: vec![interval_trace],
+            columns: io::EventLogColumns::default_style(),
+        };
+        let lifecycle_log = conversion::interval_to_lifecycle(&event_log).unwrap();
+        let transitions = lifecycle_log.traces[0]
+            .events
+            .iter()
+            .map(|e| e.lifecycle.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            transitions,
+            [
+                "schedule",
+                "start",
+                "suspend",
+                "resume",
+                "complete",
+                "start",
+                "ate_abort",
+                "start",
+                "pi_abort"
+            ]
+        );
+    }
 }
@@ -0,0 +1,197 @@
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Malformed XML. `position` is the byte offset in the input when known.
+    Xml {
+        position: Option<usize>,
+        source: quick_xml::Error,
+    },
+
+    /// An attribute value that does not match its declared type, e.g., `<int value="abc"/>`, or a value of a
+    /// column of lifecycle details that is out of range. `location` names the byte, line or row.
+    InvalidAttribute {
+        key: String,
+        value: String,
+        location: String,
+    },
+
+    /// A timestamp that is missing or matches none of the accepted formats. `location` names the row or event.
+    InvalidTimestamp {
+        value: Option<String>,
+        location: String,
+    },
+
+    /// A timestamp without an offset that occurs twice (`ambiguous`) or not at all in `timezone` because of a DST
+    /// change.
+    LocalTime {
+        value: String,
+        timezone: String,
+        location: String,
+        ambiguous: bool,
+    },
+
+    /// Malformed CSV, e.g., a row with a different number of fields than the header.
+    Csv {
+        line: Option<u64>,
+        source: csv::Error,
+    },
+
+    /// A malformed Parquet file, or a column that cannot be read as the type of its field.
+    Parquet {
+        source: parquet::errors::ParquetError,
+    },
+
+    /// A mapped column is absent from the CSV header or the Parquet schema.
+    MissingColumn {
+        column: String,
+    },
+
+    /// A column that a trace needs, but the header of a CSV or Parquet file lacks, as it was written from the
+    /// declared columns and the first `scanned` traces of the log.
+    UndeclaredColumn {
+        column: String,
+        case: String,
+        scanned: usize,
+    },
+
+    /// An activity in a case has a lifecycle event without a matching counterpart.
+    UnpairedEvents {
+        case: String,
+        activity: String,
+        events: usize,
+    },
+
+    /// A file whose event log format cannot be told from its extension or content. `path` is quoted, or names
+    /// standard input or output.
+    UnknownFormat {
+        path: String,
+    },
+
+    /// An OCEL log that does not follow the OCEL 2.0 schema, e.g., malformed JSON or a value that does not match
+    /// its declared attribute type.
+    Ocel {
+        message: String,
+    },
+
+    /// An object type to flatten an OCEL log onto that the log does not have.
+    MissingObjectType {
+        object_type: String,
+    },
+
+    /// An event log format name that is not registered.
+    UnsupportedFormat {
+        name: String,
+    },
+
+    /// A configuration file that cannot be parsed or has invalid values.
+    Config {
+        path: String,
+        message: String,
+    },
+
+    Io(std::io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Xml {
+                position: Some(position),
+                source,
+            } => write!(f, "XML error at byte {position}: {source}"),
+            Error::Xml { position: None, source } => write!(f, "XML error: {source}"),
+            Error::InvalidAttribute { key, value, location } => {
+                write!(f, "invalid value '{value}' for attribute '{key}' at {location}")
+            }
+            Error::InvalidTimestamp {
+                value: Some(value),
+                location,
+            } => write!(f, "invalid timestamp '{value}' at {location}"),
+            Error::InvalidTimestamp { value: None, location } => write!(f, "missing timestamp at {location}"),
+            Error::LocalTime {
+                value,
+                timezone,
+                location,
+                ambiguous,
+            } => {
+                let problem = if *ambiguous { "ambiguous" } else { "non-existent" };
+                write!(f, "{problem} local time '{value}' in {timezone} at {location}")
+            }
+            Error::Csv {
+                line: Some(line),
+                source,
+            } => write!(f, "CSV error at line {line}: {source}"),
+            Error::Csv { line: None, source } => write!(f, "CSV error: {source}"),
+            // Parquet errors start with their own "Parquet error:" prefix
+            Error::Parquet { source } => write!(f, "{source}"),
+            Error::MissingColumn { column } => write!(f, "column '{column}' is missing from the input"),
+            Error::UndeclaredColumn { column, case, scanned } => write!(
+                f,
+                "case '{case}' needs column '{column}', which is neither declared nor in the first {scanned} traces"
+            ),
+            Error::UnpairedEvents { case, activity, events } => write!(
+                f,
+                "case '{case}' has {events} lifecycle events for activity '{activity}' that cannot be paired"
+            ),
+            Error::Ocel { message } => write!(f, "invalid OCEL log: {message}"),
+            Error::MissingObjectType { object_type } => {
+                write!(f, "object type '{object_type}' is missing from the OCEL log")
+            }
+            Error::UnknownFormat { path } => write!(f, "cannot tell the event log format of {path}"),
+            Error::UnsupportedFormat { name } => write!(f, "unsupported event log format '{name}'"),
+            Error::Config { path, message } => write!(f, "invalid config file '{path}': {message}"),
+            Error::Io(e) => write!(f, "IO error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Xml { source, .. } => Some(source),
+            Error::Csv { source, .. } => Some(source),
+            Error::Parquet { source } => Some(source),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<quick_xml::Error> for Error {
+    fn from(e: quick_xml::Error) -> Self {
+        match e {
+            quick_xml::Error::Io(e) => Error::Io(e),
+            source => Error::Xml { position: None, source },
+        }
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Self {
+        Error::Csv {
+            line: e.position().map(|p| p.line()),
+            source: e,
+        }
+    }
+}
+
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(e: parquet::errors::ParquetError) -> Self {
+        Error::Parquet { source: e }
+    }
+}
+
+impl From<arrow_schema::ArrowError> for Error {
+    fn from(e: arrow_schema::ArrowError) -> Self {
+        Error::Parquet { source: e.into() }
+    }
+}
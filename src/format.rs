@@ -0,0 +1,841 @@
+use std::cell::OnceCell;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use clap::ValueEnum;
+
+use crate::config::Config;
+use crate::conversion::{self, ConversionOptions, UnpairedSummary, VariantNumbering};
+use crate::error::{Error, Result};
+use crate::io::{self as csv_io, ColumnPreset, CsvShape, EventLogColumns};
+use crate::mxml;
+use crate::ocel::{self, ObjectMapping, OcelSyntax};
+use crate::parquet_io;
+use crate::timestamp::TimestampParser;
+use crate::xes::attribute::Attribute;
+use crate::xes::header::LogHeader;
+use crate::xes::{interval, lifecycle};
+
+/// Traces in the lifecycle model, which every format is read into and written from.
+pub type Traces = Box<dyn Iterator<Item = Result<lifecycle::Trace>>>;
+
+/// Reads the log header and traces from the start on every call, for writers that need several passes over a log.
+pub type TraceSource<'a> = dyn Fn() -> Result<(LogHeader, Traces)> + 'a;
+
+/// Traces that the CSV and Parquet writers read ahead to find the attribute and lifecycle detail columns of a log, so
+/// they write it in a single pass with at most this many traces in memory. Attributes that only later traces have
+/// need to be declared by a `<global>` of the log or in the `attributes` of the config.
+pub const COLUMN_SCAN_TRACES: usize = 1000;
+
+/// Opens the input from the start on every call, for readers that need several passes over a file.
+pub type InputSource<'a> = dyn Fn() -> io::Result<Box<dyn Read>> + 'a;
+
+pub trait EventLogReader {
+    /// Returns the log header and the traces of the input one at a time. Formats without a header return the
+    /// default one.
+    fn read(&self, input: &InputSource) -> Result<(LogHeader, Traces)>;
+
+    /// Whether `read` opens the input more than once. Input that can only be read once, such as standard input, is
+    /// then copied to a temporary file first. The built-in readers read their input once.
+    fn needs_several_passes(&self) -> bool {
+        false
+    }
+}
+
+pub trait EventLogWriter {
+    /// Writes the log of `traces`. Formats with a header write the header of the log, so it survives a conversion.
+    fn write(&self, traces: &TraceSource, output: &mut dyn Write) -> Result<()>;
+
+    /// Whether `write` calls `traces` more than once, which reads the input again. The built-in writers read the
+    /// traces once.
+    fn needs_several_passes(&self) -> bool {
+        false
+    }
+}
+
+/// An event log format: its name, how its files are recognized, and how it is read and written.
+#[derive(Clone)]
+pub struct Format {
+    pub name: &'static str,
+
+    /// File name extensions without the leading dot, e.g., `xes` or `xes.gz`.
+    pub extensions: &'static [&'static str],
+
+    /// Whether a file that starts with these bytes is in the format.
+    pub detect: fn(&[u8]) -> bool,
+
+    pub reader: Rc<dyn EventLogReader>,
+    pub writer: Rc<dyn EventLogWriter>,
+}
+
+/// Formats by name, file extension and content. Later registrations take precedence, so a format can replace a
+/// built-in one, and content checks run from the most recently registered format to the first.
+#[derive(Clone, Default)]
+pub struct FormatRegistry {
+    formats: Vec<Format>,
+}
+
+impl FormatRegistry {
+    /// A registry of the built-in formats with default options.
+    pub fn builtin() -> FormatRegistry {
+        let mut registry = FormatRegistry::default();
+        registry.register(CsvFormat::new(Config::default(), ConversionOptions::default()).format());
+        registry.register(XesFormat::default().format());
+        registry.register(MxmlFormat::default().format());
+        for syntax in [OcelSyntax::Json, OcelSyntax::Xml] {
+            registry.register(
+                OcelFormat {
+                    syntax,
+                    ..Default::default()
+                }
+                .format(),
+            );
+        }
+        registry.register(ParquetFormat::new(Config::default(), ConversionOptions::default()).format());
+        registry
+    }
+
+    pub fn register(&mut self, format: Format) {
+        self.formats.push(format);
+    }
+
+    pub fn formats(&self) -> impl Iterator<Item = &Format> {
+        self.formats.iter().rev()
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&Format> {
+        self.formats().find(|format| format.name == name)
+    }
+
+    /// The format with the longest extension that the file name ends with, ignoring case.
+    pub fn by_extension(&self, path: &Path) -> Option<&Format> {
+        let file_name = path.file_name()?.to_str()?.to_lowercase();
+        self.formats()
+            .flat_map(|format| format.extensions.iter().map(move |extension| (format, extension)))
+            .filter(|(_, extension)| file_name.ends_with(&format!(".{extension}")))
+            .max_by_key(|(_, extension)| extension.len())
+            .map(|(format, _)| format)
+    }
+
+    /// The format of a file that starts with these bytes.
+    pub fn detect(&self, start: &[u8]) -> Option<&Format> {
+        self.formats().find(|format| (format.detect)(start))
+    }
+}
+
+/// Numbers the activity sequences of traces without a variant, see [`VariantNumbering`].
+pub fn number_variants(traces: Traces) -> Traces {
+    let mut variants = VariantNumbering::default();
+    Box::new(traces.map(move |trace| {
+        trace.map(|mut trace| {
+            variants.assign_lifecycle(&mut trace);
+            trace
+        })
+    }))
+}
+
+/// Whether the bytes start with an XML element or declaration, after an optional byte order mark.
+pub fn is_xml(start: &[u8]) -> bool {
+    let start = start.strip_prefix(b"\xef\xbb\xbf").unwrap_or(start);
+    start.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'<')
+}
+
+/// XES files, read and written one trace at a time.
+#[derive(Debug, Clone, Default)]
+pub struct XesFormat {
+    /// Leave out events named Start or End.
+    pub filter_start_end_events: bool,
+
+    /// Timezones for `date` attributes, see [`lifecycle::TraceReader::with_timestamps`].
+    pub timestamps: TimestampParser,
+
+    /// Spaces per nesting level of written logs, which are otherwise written on a single line.
+    pub indent: Option<usize>,
+}
+
+impl XesFormat {
+    pub fn format(self) -> Format {
+        let xes = Rc::new(self);
+        Format {
+            name: "xes",
+            extensions: &["xes"],
+            detect: is_xml,
+            reader: xes.clone(),
+            writer: xes,
+        }
+    }
+}
+
+impl EventLogReader for XesFormat {
+    fn read(&self, input: &InputSource) -> Result<(LogHeader, Traces)> {
+        let mut traces = lifecycle::TraceReader::new(BufReader::new(input()?), self.filter_start_end_events)
+            .with_timestamps(self.timestamps.clone());
+        let header = traces.header()?.clone();
+        Ok((header, Box::new(traces)))
+    }
+}
+
+impl EventLogWriter for XesFormat {
+    fn write(&self, traces: &TraceSource, output: &mut dyn Write) -> Result<()> {
+        let (header, traces) = traces()?;
+        let mut xes_writer = match self.indent {
+            Some(indent) => csv_io::XesWriter::indented(output, &header, indent)?,
+            None => csv_io::XesWriter::new(output, &header)?,
+        };
+        for trace in traces {
+            xes_writer.write_trace(&trace?)?;
+        }
+        xes_writer.finish()?.flush()?;
+        Ok(())
+    }
+}
+
+/// ProM 5 MXML files, read and written one process instance at a time.
+#[derive(Debug, Clone, Default)]
+pub struct MxmlFormat {
+    /// Leave out events named Start or End.
+    pub filter_start_end_events: bool,
+
+    /// Timezones for timestamps, see [`mxml::MxmlReader::with_timestamps`].
+    pub timestamps: TimestampParser,
+
+    /// Spaces per nesting level of written logs, which are otherwise written on a single line.
+    pub indent: Option<usize>,
+}
+
+impl MxmlFormat {
+    pub fn format(self) -> Format {
+        let mxml = Rc::new(self);
+        Format {
+            name: "mxml",
+            extensions: &["mxml"],
+            detect: mxml::is_mxml,
+            reader: mxml.clone(),
+            writer: mxml,
+        }
+    }
+}
+
+impl EventLogReader for MxmlFormat {
+    fn read(&self, input: &InputSource) -> Result<(LogHeader, Traces)> {
+        let mut traces = mxml::MxmlReader::new(BufReader::new(input()?), self.filter_start_end_events)
+            .with_timestamps(self.timestamps.clone());
+        let header = traces.header()?.clone();
+        Ok((header, Box::new(traces)))
+    }
+}
+
+impl EventLogWriter for MxmlFormat {
+    fn write(&self, traces: &TraceSource, output: &mut dyn Write) -> Result<()> {
+        // The concept:name of the header is the process id
+        let (header, traces) = traces()?;
+        let mut mxml_writer = match self.indent {
+            Some(indent) => mxml::MxmlWriter::indented(output, &header, indent)?,
+            None => mxml::MxmlWriter::new(output, &header)?,
+        };
+        for trace in traces {
+            mxml_writer.write_trace(&trace?)?;
+        }
+        mxml_writer.finish()?.flush()?;
+        Ok(())
+    }
+}
+
+/// OCEL 2.0 JSON or XML files. They are flattened onto the case object type when read and lifted from cases when
+/// written, see [`ocel::OcelLog::flatten`] and [`ocel::OcelLog::lift`].
+#[derive(Debug, Clone, Default)]
+pub struct OcelFormat {
+    pub syntax: OcelSyntax,
+    pub mapping: ObjectMapping,
+
+    /// Timezones for timestamps, which are always ISO 8601.
+    pub timestamps: TimestampParser,
+
+    /// Spaces per nesting level of written logs, which are otherwise written on a single line.
+    pub indent: Option<usize>,
+}
+
+impl OcelFormat {
+    pub fn format(self) -> Format {
+        let ocel = Rc::new(self);
+        match ocel.syntax {
+            OcelSyntax::Json => Format {
+                name: "ocel-json",
+                extensions: &["jsonocel", "ocel.json"],
+                detect: ocel::is_ocel_json,
+                reader: ocel.clone(),
+                writer: ocel,
+            },
+            OcelSyntax::Xml => Format {
+                name: "ocel-xml",
+                extensions: &["xmlocel", "ocel.xml"],
+                detect: ocel::is_ocel_xml,
+                reader: ocel.clone(),
+                writer: ocel,
+            },
+        }
+    }
+}
+
+impl EventLogReader for OcelFormat {
+    fn read(&self, input: &InputSource) -> Result<(LogHeader, Traces)> {
+        let timestamps = TimestampParser {
+            formats: TimestampParser::default().formats,
+            ..self.timestamps.clone()
+        };
+        let input = BufReader::new(input()?);
+        let log = match self.syntax {
+            OcelSyntax::Json => ocel::OcelLog::from_json(input, &timestamps)?,
+            OcelSyntax::Xml => ocel::OcelLog::from_xml(input, &timestamps)?,
+        };
+        let log = log.flatten(&self.mapping)?;
+        Ok((log.header, Box::new(log.traces.into_iter().map(Ok))))
+    }
+}
+
+impl EventLogWriter for OcelFormat {
+    fn write(&self, traces: &TraceSource, output: &mut dyn Write) -> Result<()> {
+        let (_, traces) = traces()?;
+        let log = ocel::OcelLog::lift(traces, &self.mapping)?;
+        match self.syntax {
+            OcelSyntax::Json => log.to_json(&mut *output, self.indent)?,
+            OcelSyntax::Xml => log.to_xml(&mut *output, self.indent)?,
+        }
+        output.flush()?;
+        Ok(())
+    }
+}
+
+/// Parquet files in any of the CSV shapes, with typed columns named by the column mapping of a config. See
+/// [`parquet_io::ParquetWriter`] for the column types.
+#[derive(Debug, Clone)]
+pub struct ParquetFormat {
+    pub config: Config,
+
+    /// How lifecycle events are paired into the rows of the interval shape.
+    pub conversion: ConversionOptions,
+
+    /// Rows per row group of written files, and per record batch of read ones.
+    pub row_group_size: usize,
+
+    /// Receives notes on unpaired events. They are dropped by default.
+    pub notify: fn(&str),
+}
+
+impl ParquetFormat {
+    pub fn new(config: Config, conversion: ConversionOptions) -> ParquetFormat {
+        ParquetFormat {
+            config,
+            conversion,
+            row_group_size: parquet_io::DEFAULT_ROW_GROUP_SIZE,
+            notify: |_| (),
+        }
+    }
+
+    pub fn with_notify(mut self, notify: fn(&str)) -> ParquetFormat {
+        self.notify = notify;
+        self
+    }
+
+    pub fn with_row_group_size(mut self, row_group_size: usize) -> ParquetFormat {
+        self.row_group_size = row_group_size;
+        self
+    }
+
+    pub fn format(self) -> Format {
+        let parquet = Rc::new(self);
+        Format {
+            name: "parquet",
+            extensions: &["parquet"],
+            detect: parquet_io::is_parquet,
+            reader: parquet.clone(),
+            writer: parquet,
+        }
+    }
+}
+
+impl EventLogReader for ParquetFormat {
+    fn read(&self, input: &InputSource) -> Result<(LogHeader, Traces)> {
+        // The footer with the schema is at the end of the file, so the input is spooled to a seekable file first
+        let mut file = tempfile::tempfile()?;
+        io::copy(&mut input()?, &mut file)?;
+
+        let headers = parquet_io::read_field_names(file.try_clone()?)?;
+        let columns = self.config.event_log_columns(Some(&headers));
+        let timezone = self.config.timestamp_parser().output_timezone;
+        let traces = parquet_io::ParquetTraceReader::new(file, columns, timezone, self.row_group_size)?;
+        Ok((LogHeader::default(), Box::new(traces)))
+    }
+}
+
+impl EventLogWriter for ParquetFormat {
+    fn write(&self, traces: &TraceSource, output: &mut dyn Write) -> Result<()> {
+        let columns = self.config.event_log_columns(None);
+        let (header, traces) = traces()?;
+
+        // The schema lists every attribute column with its type, so they are collected from the declared columns
+        // and the first traces, and later traces are checked against them
+        let mut attribute_columns = Vec::new();
+        parquet_io::add_lifecycle_attribute_columns(&mut attribute_columns, &declared_trace(&header));
+        let declared_keys: Vec<String> = columns.attributes.keys().cloned().collect();
+        let add_declared_keys = |attribute_columns: &mut Vec<parquet_io::AttributeColumn>| {
+            for key in &declared_keys {
+                if !attribute_columns.iter().any(|column| &column.key == key) {
+                    attribute_columns.push(parquet_io::AttributeColumn {
+                        key: key.clone(),
+                        value_type: parquet_io::ValueType::String,
+                        is_trace_attribute: false,
+                    });
+                }
+            }
+        };
+
+        if columns.shape != CsvShape::Interval {
+            let mut traces = traces;
+            let prefix = traces.by_ref().take(COLUMN_SCAN_TRACES).collect::<Result<Vec<_>>>()?;
+            for trace in &prefix {
+                parquet_io::add_lifecycle_attribute_columns(&mut attribute_columns, trace);
+            }
+            add_declared_keys(&mut attribute_columns);
+
+            let checked = attribute_columns.clone();
+            let details = csv_io::DetailColumns::default();
+            let mut parquet_writer =
+                parquet_io::ParquetWriter::new(&mut *output, columns, details, attribute_columns, self.row_group_size)?;
+            for trace in prefix.into_iter().map(Ok).chain(traces) {
+                let trace = trace?;
+                check_attributes(&trace.case, lifecycle_attributes(&trace), |attribute, is_trace_attribute| {
+                    parquet_io::has_column(&checked, attribute, is_trace_attribute)
+                })?;
+                parquet_writer.write_lifecycle_trace(&trace)?;
+            }
+            parquet_writer.finish()?.flush()?;
+            return Ok(());
+        }
+
+        let mut traces =
+            traces.map(|trace| conversion::lifecycle_trace_to_interval_with_summary(&trace?, &self.conversion));
+        let prefix = traces.by_ref().take(COLUMN_SCAN_TRACES).collect::<Result<Vec<_>>>()?;
+        let mut details = csv_io::DetailColumns::default();
+        for (trace, _) in &prefix {
+            details.add(trace);
+            parquet_io::add_interval_attribute_columns(&mut attribute_columns, trace);
+        }
+        add_declared_keys(&mut attribute_columns);
+
+        let checked = attribute_columns.clone();
+        let detail_names = details.names();
+        let mut parquet_writer =
+            parquet_io::ParquetWriter::new(&mut *output, columns, details, attribute_columns, self.row_group_size)?;
+        for item in prefix.into_iter().map(Ok).chain(traces) {
+            let (trace, summary) = item?;
+            check_details(details, &trace)?;
+            check_attributes(&trace.case, interval_attributes(&trace), |attribute, is_trace_attribute| {
+                detail_names.contains(&attribute.key.as_str())
+                    || parquet_io::has_column(&checked, attribute, is_trace_attribute)
+            })?;
+            notify_unpaired(self.notify, &trace.case, &summary, &self.conversion);
+            parquet_writer.write_interval_trace(&trace)?;
+        }
+        parquet_writer.finish()?.flush()?;
+        Ok(())
+    }
+}
+
+/// CSV files in any of the CSV shapes, with the column mapping, timestamp formats and dialect of a config.
+pub struct CsvFormat {
+    pub config: Config,
+
+    /// How lifecycle events are paired into the rows of the interval shape.
+    pub conversion: ConversionOptions,
+
+    /// Receives notes on detected presets and unpaired events. They are dropped by default.
+    pub notify: fn(&str),
+
+    input_columns: OnceCell<EventLogColumns>,
+    output_columns: OnceCell<EventLogColumns>,
+}
+
+impl CsvFormat {
+    pub fn new(config: Config, conversion: ConversionOptions) -> CsvFormat {
+        CsvFormat {
+            config,
+            conversion,
+            notify: |_| (),
+            input_columns: OnceCell::new(),
+            output_columns: OnceCell::new(),
+        }
+    }
+
+    pub fn with_notify(mut self, notify: fn(&str)) -> CsvFormat {
+        self.notify = notify;
+        self
+    }
+
+    /// The column mapping of the input, resolved from its header when it is first read. The format is meant for a
+    /// single input, as later reads reuse the mapping.
+    pub fn input_columns(&self) -> Option<&EventLogColumns> {
+        self.input_columns.get()
+    }
+
+    /// The column mapping of written logs, once one was written.
+    pub fn output_columns(&self) -> Option<&EventLogColumns> {
+        self.output_columns.get()
+    }
+
+    /// A format whose reader and writer share this format, so the resolved column mappings can be read back.
+    pub fn format(self) -> Format {
+        Rc::new(self).shared_format()
+    }
+
+    pub fn shared_format(self: Rc<Self>) -> Format {
+        Format {
+            name: "csv",
+            extensions: &["csv"],
+            // CSV has no signature, so any content is taken as CSV unless another format claims it
+            detect: |_| true,
+            reader: self.clone(),
+            writer: self,
+        }
+    }
+}
+
+impl EventLogReader for CsvFormat {
+    fn read(&self, input: &InputSource) -> Result<(LogHeader, Traces)> {
+        let dialect = self.config.csv.dialect();
+
+        // The header, the extra column types and the sorting by case all come from a single pass over the input
+        let columns = |headers: &csv::StringRecord| {
+            let columns = self.input_columns.get_or_init(|| {
+                let detected = self.config.detect_preset(headers);
+                if let Some(preset) = detected.filter(|&preset| preset != ColumnPreset::Default) {
+                    (self.notify)(&format!(
+                        "detected column preset '{}'",
+                        preset.to_possible_value().unwrap().get_name()
+                    ));
+                }
+                self.config.event_log_columns(Some(headers))
+            });
+            columns.clone()
+        };
+        let traces = csv_io::CsvTraceReader::single_pass(
+            input()?,
+            &dialect,
+            columns,
+            self.config.timestamp_parser(),
+            &self.conversion,
+        )?;
+        if self.input_columns.get().unwrap().shape == CsvShape::Interval {
+            let traces = traces.map(|trace| trace.map(|trace| conversion::interval_trace_to_lifecycle(&trace)));
+            Ok((LogHeader::default(), Box::new(traces)))
+        } else {
+            // Each row becomes one lifecycle event as it is
+            Ok((LogHeader::default(), Box::new(traces.lifecycle_traces())))
+        }
+    }
+}
+
+impl EventLogWriter for CsvFormat {
+    fn write(&self, traces: &TraceSource, output: &mut dyn Write) -> Result<()> {
+        let columns = self
+            .output_columns
+            .get_or_init(|| self.config.event_log_columns(None))
+            .clone();
+        let dialect = self.config.csv.dialect();
+        let (header, traces) = traces()?;
+
+        // The CSV header lists every attribute key, so they are collected from the declared columns and the first
+        // traces, and later traces are checked against them
+        let mut attribute_columns = Vec::new();
+        csv_io::add_lifecycle_attribute_columns(&mut attribute_columns, &declared_trace(&header));
+        let add_declared_keys = |attribute_columns: &mut Vec<String>| {
+            for key in columns.attributes.keys() {
+                if !attribute_columns.contains(key) {
+                    attribute_columns.push(key.clone());
+                }
+            }
+        };
+
+        if columns.shape != CsvShape::Interval {
+            // Single-timestamp rows are the events themselves, so there is nothing to pair
+            let mut traces = traces;
+            let prefix = traces.by_ref().take(COLUMN_SCAN_TRACES).collect::<Result<Vec<_>>>()?;
+            for trace in &prefix {
+                csv_io::add_lifecycle_attribute_columns(&mut attribute_columns, trace);
+            }
+            add_declared_keys(&mut attribute_columns);
+
+            let checked = attribute_columns.clone();
+            let mut csv_writer =
+                csv_io::LifecycleCsvWriter::with_dialect(output, attribute_columns, columns.shape, &dialect)
+                    .with_columns(columns);
+            for trace in prefix.into_iter().map(Ok).chain(traces) {
+                let trace = trace?;
+                check_attributes(&trace.case, lifecycle_attributes(&trace), |attribute, _| {
+                    checked.contains(&attribute.key)
+                })?;
+                csv_writer.write_trace(&trace)?;
+            }
+            return csv_writer.flush();
+        }
+
+        // The header also lists the lifecycle details that the first traces have
+        let mut traces =
+            traces.map(|trace| conversion::lifecycle_trace_to_interval_with_summary(&trace?, &self.conversion));
+        let prefix = traces.by_ref().take(COLUMN_SCAN_TRACES).collect::<Result<Vec<_>>>()?;
+        let mut details = csv_io::DetailColumns::default();
+        for (trace, _) in &prefix {
+            csv_io::add_attribute_columns(&mut attribute_columns, trace);
+            details.add(trace);
+        }
+        add_declared_keys(&mut attribute_columns);
+
+        let checked = attribute_columns.clone();
+        let detail_names = details.names();
+        let mut csv_writer = csv_io::IntervalCsvWriter::with_dialect(output, attribute_columns, &dialect)
+            .with_columns(columns)
+            .with_details(details);
+        for item in prefix.into_iter().map(Ok).chain(traces) {
+            let (trace, summary) = item?;
+            check_details(details, &trace)?;
+            check_attributes(&trace.case, interval_attributes(&trace), |attribute, _| {
+                checked.contains(&attribute.key) || detail_names.contains(&attribute.key.as_str())
+            })?;
+            notify_unpaired(self.notify, &trace.case, &summary, &self.conversion);
+            csv_writer.write_trace(&trace)?;
+        }
+        csv_writer.flush()
+    }
+}
+
+/// A trace with one event that has the `<global>` attributes of a log header, whose keys are declared columns of
+/// written CSV and Parquet files even if no trace has them.
+fn declared_trace(header: &LogHeader) -> lifecycle::Trace {
+    let mut trace = lifecycle::Trace::default();
+    let mut event = lifecycle::Event::default();
+    for attribute in &header.trace_globals {
+        trace.set_attribute(attribute.clone());
+    }
+    for attribute in &header.event_globals {
+        event.set_attribute(attribute.clone());
+    }
+    trace.events.push(event);
+    trace
+}
+
+fn lifecycle_attributes(trace: &lifecycle::Trace) -> impl Iterator<Item = (&Attribute, bool)> {
+    let event_attributes = trace.events.iter().flat_map(|event| &event.attributes);
+    let trace_attributes = trace.attributes.iter().map(|attribute| (attribute, true));
+    trace_attributes.chain(event_attributes.map(|attribute| (attribute, false)))
+}
+
+fn interval_attributes(trace: &interval::Trace) -> impl Iterator<Item = (&Attribute, bool)> {
+    let event_attributes = trace.events.iter().flat_map(|event| &event.attributes);
+    let trace_attributes = trace.attributes.iter().map(|attribute| (attribute, true));
+    trace_attributes.chain(event_attributes.map(|attribute| (attribute, false)))
+}
+
+/// Fails on a lifecycle detail of a trace after the column scan that the written header has no column for.
+fn check_details(details: csv_io::DetailColumns, trace: &interval::Trace) -> Result<()> {
+    let mut needed = details;
+    needed.add(trace);
+    let known = details.names();
+    match needed.names().into_iter().find(|name| !known.contains(name)) {
+        Some(column) => Err(undeclared_column(&trace.case, column)),
+        None => Ok(()),
+    }
+}
+
+/// Fails on an attribute of a trace after the column scan that `has_column` finds no column for, given whether it
+/// is a trace attribute.
+fn check_attributes<'a>(
+    case: &str,
+    mut attributes: impl Iterator<Item = (&'a Attribute, bool)>,
+    has_column: impl Fn(&Attribute, bool) -> bool,
+) -> Result<()> {
+    match attributes.find(|&(attribute, is_trace_attribute)| !has_column(attribute, is_trace_attribute)) {
+        Some((attribute, _)) => Err(undeclared_column(case, &attribute.key)),
+        None => Ok(()),
+    }
+}
+
+fn undeclared_column(case: &str, column: &str) -> Error {
+    Error::UndeclaredColumn {
+        column: column.to_string(),
+        case: case.to_string(),
+        scanned: COLUMN_SCAN_TRACES,
+    }
+}
+
+/// Notes the events of a case that the unpaired policy handled, if any.
+fn notify_unpaired(notify: fn(&str), case: &str, summary: &UnpairedSummary, conversion: &ConversionOptions) {
+    if !summary.is_empty() {
+        notify(&format!(
+            "case '{}': {} start(s) without complete, {} complete(s) without start, {} unknown transition(s), \
+             policy '{}'",
+            case,
+            summary.starts,
+            summary.completes,
+            summary.unknown,
+            conversion.unpaired.to_possible_value().unwrap().get_name()
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn test_log() -> Vec<u8> {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("event_logs");
+        path.push("Production.xes");
+        std::fs::read(path).unwrap()
+    }
+
+    fn input(data: Vec<u8>) -> impl Fn() -> io::Result<Box<dyn Read>> {
+        move || Ok(Box::new(io::Cursor::new(data.clone())) as Box<dyn Read>)
+    }
+
+    #[test]
+    fn test_format_registry() {
+        let registry = FormatRegistry::builtin();
+
+        assert_eq!(registry.by_name("csv").unwrap().name, "csv");
+        assert_eq!(registry.by_extension(Path::new("dir/log.XES")).unwrap().name, "xes");
+        assert!(registry.by_extension(Path::new("log.txt")).is_none());
+        assert_eq!(
+            registry.detect(b"\xef\xbb\xbf\n<?xml version=\"1.0\"?>").unwrap().name,
+            "xes"
+        );
+        assert_eq!(registry.detect(b"case,activity\n").unwrap().name, "csv");
+        assert_eq!(registry.by_extension(Path::new("archive/Production.MXML")).unwrap().name, "mxml");
+        assert_eq!(registry.detect(b"<?xml version=\"1.0\"?>\n<WorkflowLog>").unwrap().name, "mxml");
+        assert_eq!(registry.by_extension(Path::new("orders.jsonocel")).unwrap().name, "ocel-json");
+        assert_eq!(registry.detect(b"{\"objectTypes\": []").unwrap().name, "ocel-json");
+        assert_eq!(registry.detect(b"<log>\n  <object-types>").unwrap().name, "ocel-xml");
+        assert_eq!(registry.by_extension(Path::new("orders.parquet")).unwrap().name, "parquet");
+        assert_eq!(registry.detect(b"PAR1\x15\x04").unwrap().name, "parquet");
+    }
+
+    #[test]
+    fn test_custom_format() {
+        struct CaseList;
+
+        impl EventLogWriter for CaseList {
+            fn write(&self, traces: &TraceSource, output: &mut dyn Write) -> Result<()> {
+                for trace in traces()?.1 {
+                    writeln!(output, "{}", trace?.case)?;
+                }
+                Ok(())
+            }
+        }
+
+        let mut registry = FormatRegistry::builtin();
+        let xes = registry.by_name("xes").unwrap().clone();
+        registry.register(Format {
+            name: "cases",
+            extensions: &["cases.txt"],
+            detect: |_| false,
+            reader: xes.reader.clone(),
+            writer: Rc::new(CaseList),
+        });
+
+        let from = registry.by_extension(Path::new("Production.xes")).unwrap();
+        let to = registry.by_extension(Path::new("production.cases.txt")).unwrap();
+        let log = input(test_log());
+        let mut output = Vec::new();
+        to.writer.write(&|| from.reader.read(&log), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().next(), Some("1"));
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let registry = FormatRegistry::builtin();
+        let (xes, csv) = (registry.by_name("xes").unwrap(), registry.by_name("csv").unwrap());
+
+        let log = input(test_log());
+        let mut csv_output = Vec::new();
+        let numbered = || {
+            let (header, traces) = xes.reader.read(&log)?;
+            Ok((header, number_variants(traces)))
+        };
+        csv.writer.write(&numbered, &mut csv_output).unwrap();
+
+        let log = input(csv_output);
+        let mut xes_output = Vec::new();
+        xes.writer.write(&|| csv.reader.read(&log), &mut xes_output).unwrap();
+
+        let log = input(xes_output);
+        let traces = xes.reader.read(&log).unwrap().1.collect::<Result<Vec<_>>>().unwrap();
+        let original = input(test_log());
+        let original = xes.reader.read(&original).unwrap().1.collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(traces.len(), original.len());
+        assert_eq!(traces[0].variant, "1");
+        assert_eq!(traces[0].events.len(), original[0].events.len());
+    }
+
+    #[test]
+    fn test_column_scan() {
+        use crate::error::Error;
+        use crate::timestamp::TimestampFormat;
+        use crate::xes::attribute::AttributeValue;
+
+        let traces = |header: LogHeader| {
+            let traces = (0..=COLUMN_SCAN_TRACES).map(|case| {
+                let start = lifecycle::Event {
+                    activity: "A".to_string(),
+                    timestamp: TimestampFormat::Iso8601.parse("2022-01-03T08:00:00.000+00:00"),
+                    lifecycle: "start".to_string(),
+                    ..lifecycle::Event::default()
+                };
+                let mut complete = lifecycle::Event {
+                    lifecycle: "complete".to_string(),
+                    ..start.clone()
+                };
+                if case == COLUMN_SCAN_TRACES {
+                    complete.attributes.insert(Attribute::new("cost", AttributeValue::Int(5)));
+                }
+                Ok(lifecycle::Trace {
+                    case: case.to_string(),
+                    events: vec![start, complete],
+                    ..lifecycle::Trace::default()
+                })
+            });
+            (header, Box::new(traces.collect::<Vec<_>>().into_iter()) as Traces)
+        };
+        let reads = std::cell::Cell::new(0);
+        let source = |header: LogHeader| {
+            let reads = &reads;
+            move || {
+                reads.set(reads.get() + 1);
+                Ok(traces(header.clone()))
+            }
+        };
+        let registry = FormatRegistry::builtin();
+        let csv = registry.by_name("csv").unwrap();
+
+        let mut output = Vec::new();
+        let result = csv.writer.write(&source(LogHeader::default()), &mut output);
+        assert!(matches!(
+            result,
+            Err(Error::UndeclaredColumn { column, case, .. }) if column == "cost" && case == "1000"
+        ));
+
+        let header = LogHeader {
+            event_globals: vec![Attribute::new("cost", AttributeValue::Int(0))],
+            ..LogHeader::default()
+        };
+        let mut output = Vec::new();
+        csv.writer.write(&source(header), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.lines().next().unwrap().ends_with(",cost"));
+        assert!(output.lines().last().unwrap().ends_with(",5"));
+        assert_eq!(reads.get(), 2);
+    }
+}
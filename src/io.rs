@@ -1,148 +1,1432 @@
-use std::collections::HashMap;
-use std::io;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufReader, Seek, Write};
+use std::path::Path;
 
-use quick_xml::events::{BytesDecl, Event as XmlEvent};
+use encoding_rs::Encoding;
+use encoding_rs_io::{DecodeReaderBytes, DecodeReaderBytesBuilder};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event as XmlEvent};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
+use crate::compression::Compression;
+use crate::error::{Error, Result};
+use crate::conversion::{self, ConversionOptions};
+use crate::timestamp::{self, Timestamp, TimestampParser};
+use crate::xes::attribute::{write_attribute, Attribute, AttributeValue, Attributes};
+use crate::xes::header::LogHeader;
 use crate::xes::{interval, lifecycle};
-use crate::xes::interval::{EventLog, Trace};
+use crate::xes::interval::{Completion, EventLog, Trace};
+
+/// Syntax of a CSV file. Applies to both reading and writing, except for the encoding, which is only used for
+/// reading. Output is always UTF-8.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvDialect {
+    pub delimiter: u8,
+    pub quote: u8,
+
+    /// Character that escapes a quote inside a quoted field. Quotes are escaped by doubling them if not set.
+    pub escape: Option<u8>,
+
+    /// Whether the first row is a header. Without one, columns are named by their position starting at 1, so that
+    /// the column mapping can refer to them as "1", "2" and so on.
+    pub has_header: bool,
+
+    /// Input encoding. If not set, input is UTF-8, or UTF-16 if it starts with a byte order mark. A UTF-8 byte
+    /// order mark is skipped either way.
+    pub encoding: Option<&'static Encoding>,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        CsvDialect {
+            delimiter: b',',
+            quote: b'"',
+            escape: None,
+            has_header: true,
+            encoding: None,
+        }
+    }
+}
+
+impl CsvDialect {
+    fn reader<R: io::Read>(&self, reader: R) -> csv::Reader<DecodeReaderBytes<R, Vec<u8>>> {
+        let decoder = DecodeReaderBytesBuilder::new().encoding(self.encoding).build(reader);
+        csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .escape(self.escape)
+            .double_quote(self.escape.is_none())
+            .has_headers(self.has_header)
+            .from_reader(decoder)
+    }
+
+    fn writer<W: io::Write>(&self, writer: W) -> csv::Writer<W> {
+        csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .escape(self.escape.unwrap_or(b'\\'))
+            .double_quote(self.escape.is_none())
+            .from_writer(writer)
+    }
+}
+
+/// How events are laid out in the rows of a CSV log.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CsvShape {
+    /// One row per activity instance, with start and end timestamp columns
+    #[default]
+    Interval,
+
+    /// One row per lifecycle transition, with a lifecycle column and a single timestamp column
+    Lifecycle,
+
+    /// One row per event with a single timestamp column. Every row is a complete event
+    Atomic,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventLogColumns {
     pub case: String,
-    pub variant: String,
+
+    /// Variant column, if the log has one.
+    pub variant: Option<String>,
     pub activity: String,
-    pub resource: String,
+
+    /// Resource column, if the log has one.
+    pub resource: Option<String>,
     pub start_time: String,
     pub end_time: String,
+
+    /// Timestamp column of the lifecycle and atomic shapes.
+    pub timestamp: String,
+
+    /// Lifecycle transition column of the lifecycle shape.
+    pub lifecycle: String,
+
+    pub shape: CsvShape,
+
+    /// CSV columns of extra attributes by attribute key, for columns not named like their attribute. Other extra
+    /// columns have the name of their attribute.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl EventLogColumns {
+    pub fn default_style() -> EventLogColumns {
+        EventLogColumns {
+            case: "case".to_string(),
+            variant: Some("variant".to_string()),
+            activity: "activity".to_string(),
+            resource: Some("resource".to_string()),
+            start_time: "start_time".to_string(),
+            end_time: "end_time".to_string(),
+            timestamp: "timestamp".to_string(),
+            lifecycle: "lifecycle".to_string(),
+            shape: CsvShape::Interval,
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    /// The CSV column of an extra attribute.
+    pub fn attribute_column<'a>(&'a self, key: &'a str) -> &'a str {
+        self.attributes.get(key).map_or(key, String::as_str)
+    }
+
+    /// The attribute key of an extra CSV column.
+    pub fn attribute_key<'a>(&'a self, column: &'a str) -> &'a str {
+        self.attributes.iter().find(|(_, c)| *c == column).map_or(column, |(key, _)| key.as_str())
+    }
+
+    /// The timestamp and lifecycle columns of the shape, in CSV output order.
+    fn shape_columns(&self) -> Vec<&str> {
+        match self.shape {
+            CsvShape::Interval => vec![&self.start_time, &self.end_time],
+            CsvShape::Lifecycle => vec![&self.timestamp, &self.lifecycle],
+            CsvShape::Atomic => vec![&self.timestamp],
+        }
+    }
+
+    /// Adapts the mapping to a CSV header: picks the first shape whose timestamp and lifecycle columns are all in
+    /// the header and drops the variant and resource columns if they are not. The shape is kept if no shape fits.
+    pub fn fit_to_headers(&mut self, headers: &csv::StringRecord) {
+        let has = |column: &str| headers.iter().any(|header| header == column);
+        let shape = [CsvShape::Interval, CsvShape::Lifecycle, CsvShape::Atomic]
+            .into_iter()
+            .find(|&shape| EventLogColumns { shape, ..self.clone() }.shape_columns().into_iter().all(has));
+        if let Some(shape) = shape {
+            self.shape = shape;
+        }
+        self.variant = self.variant.take().filter(|column| has(column));
+        self.resource = self.resource.take().filter(|column| has(column));
+    }
+}
+
+/// Column naming conventions of common process mining tools.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnPreset {
+    /// case, variant, activity, resource, start_time, end_time
+    #[default]
+    Default,
+
+    /// case:concept:name, concept:name, org:resource, start_timestamp, time:timestamp, lifecycle:transition
+    Pm4py,
+
+    /// case_id, activity, resource, start_time, end_time
+    Simod,
+
+    /// Case ID, Variant, Activity, Resource, Start Timestamp, Complete Timestamp
+    Disco,
+
+    /// _CASE_KEY, ACTIVITY_EN, USER_NAME, EVENTTIME
+    Celonis,
+
+    /// Case ID, Activity, Resource, Start Time, End Time
+    Apromore,
+}
+
+impl ColumnPreset {
+    pub fn columns(self) -> EventLogColumns {
+        let names = |case: &str, variant: Option<&str>, activity: &str, resource: &str, times: [&str; 4]| {
+            EventLogColumns {
+                case: case.to_string(),
+                variant: variant.map(str::to_string),
+                activity: activity.to_string(),
+                resource: Some(resource.to_string()),
+                start_time: times[0].to_string(),
+                end_time: times[1].to_string(),
+                timestamp: times[2].to_string(),
+                lifecycle: times[3].to_string(),
+                shape: CsvShape::Interval,
+                attributes: BTreeMap::new(),
+            }
+        };
+        match self {
+            ColumnPreset::Default => EventLogColumns::default_style(),
+            ColumnPreset::Pm4py => names(
+                "case:concept:name",
+                None,
+                "concept:name",
+                "org:resource",
+                ["start_timestamp", "time:timestamp", "time:timestamp", "lifecycle:transition"],
+            ),
+            ColumnPreset::Simod => names(
+                "case_id",
+                None,
+                "activity",
+                "resource",
+                ["start_time", "end_time", "timestamp", "lifecycle"],
+            ),
+            ColumnPreset::Disco => names(
+                "Case ID",
+                Some("Variant"),
+                "Activity",
+                "Resource",
+                ["Start Timestamp", "Complete Timestamp", "Complete Timestamp", "Lifecycle"],
+            ),
+            ColumnPreset::Celonis => names(
+                "_CASE_KEY",
+                None,
+                "ACTIVITY_EN",
+                "USER_NAME",
+                ["EVENTTIME_START", "EVENTTIME_END", "EVENTTIME", "LIFECYCLE"],
+            ),
+            ColumnPreset::Apromore => names(
+                "Case ID",
+                None,
+                "Activity",
+                "Resource",
+                ["Start Time", "End Time", "End Time", "Lifecycle"],
+            ),
+        }
+    }
+
+    /// Guesses the preset of a CSV header: the first one whose case and activity columns and the timestamp columns
+    /// of some shape are all in the header.
+    pub fn detect(headers: &csv::StringRecord) -> Option<ColumnPreset> {
+        ColumnPreset::value_variants().iter().copied().find(|preset| {
+            let has = |column: &str| headers.iter().any(|header| header == column);
+            let mut columns = preset.columns();
+            columns.fit_to_headers(headers);
+            has(&columns.case) && has(&columns.activity) && columns.shape_columns().into_iter().all(has)
+        })
+    }
+}
+
+pub fn interval_to_csv(event_log: &interval::EventLog, writer: &mut impl io::Write) -> Result<()> {
+    let mut details = DetailColumns::default();
+    for trace in &event_log.traces {
+        details.add(trace);
+    }
+    let mut csv_writer = IntervalCsvWriter::new(writer, attribute_columns(&event_log.traces)).with_details(details);
+    for trace in &event_log.traces {
+        csv_writer.write_trace(trace)?;
+    }
+    csv_writer.flush()
+}
+
+/// Keys of all trace and event attributes of the traces, in first-seen order. These become the extra CSV
+/// columns after the mapped ones.
+pub fn attribute_columns<'a>(traces: impl IntoIterator<Item = &'a interval::Trace>) -> Vec<String> {
+    let mut columns = Vec::new();
+    for trace in traces {
+        add_attribute_columns(&mut columns, trace);
+    }
+    columns
+}
+
+pub fn add_attribute_columns(columns: &mut Vec<String>, trace: &interval::Trace) {
+    let event_attributes = trace.events.iter().flat_map(|event| &event.attributes);
+    add_columns(columns, trace.attributes.iter().chain(event_attributes));
+}
+
+pub fn add_lifecycle_attribute_columns(columns: &mut Vec<String>, trace: &lifecycle::Trace) {
+    let event_attributes = trace.events.iter().flat_map(|event| &event.attributes);
+    add_columns(columns, trace.attributes.iter().chain(event_attributes));
+}
+
+fn add_columns<'a>(columns: &mut Vec<String>, attributes: impl Iterator<Item = &'a Attribute>) {
+    for attribute in attributes {
+        if !columns.contains(&attribute.key) {
+            columns.push(attribute.key.clone());
+        }
+    }
+}
+
+/// Column of how an activity instance ended: `complete`, `ate_abort` or `pi_abort`.
+pub const COMPLETION_COLUMN: &str = "completion";
+
+/// Column of the time an activity instance was scheduled or assigned.
+pub const SCHEDULED_TIME_COLUMN: &str = "scheduled_time";
+
+/// Column of the periods an activity instance was suspended, see [`interval::format_suspensions`].
+pub const SUSPENSIONS_COLUMN: &str = "suspensions";
+
+/// Column of the processing time of an activity instance in seconds, which is its duration minus the time it was
+/// suspended. It is derived from the suspensions, so it is only checked when read. Without a suspensions column it
+/// is kept as an attribute.
+pub const PROCESSING_TIME_COLUMN: &str = "processing_time";
+
+/// Which lifecycle details of activity instances the interval shape has columns for, after the end time. They are
+/// only written for logs that have them, so logs of plain start and complete events keep their usual columns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DetailColumns {
+    pub completion: bool,
+    pub scheduled_time: bool,
+
+    /// The suspensions and processing time columns.
+    pub suspensions: bool,
+}
+
+impl DetailColumns {
+    /// Adds the columns for the details of a trace: aborted instances, scheduled times and suspensions.
+    pub fn add(&mut self, trace: &interval::Trace) {
+        for event in &trace.events {
+            self.completion |= event.completion != Completion::Complete;
+            self.scheduled_time |= event.scheduled_time.is_some();
+            self.suspensions |= !event.suspensions.is_empty();
+        }
+    }
+
+    /// The names of the columns to write, in output order.
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            (self.completion, COMPLETION_COLUMN),
+            (self.scheduled_time, SCHEDULED_TIME_COLUMN),
+            (self.suspensions, SUSPENSIONS_COLUMN),
+            (self.suspensions, PROCESSING_TIME_COLUMN),
+        ]
+        .into_iter()
+        .filter_map(|(written, name)| written.then_some(name))
+        .collect()
+    }
+}
+
+/// A processing time in seconds, with milliseconds as the fraction if there are any.
+fn format_seconds(duration: chrono::Duration) -> String {
+    (duration.num_milliseconds() as f64 / 1000.0).to_string()
+}
+
+/// The value of an attribute column for an event, taken from the event or else from its trace.
+fn attribute_value(column: &str, event: &Attributes, trace: &Attributes) -> String {
+    event
+        .get(column)
+        .or_else(|| trace.get(column))
+        .and_then(|attribute| attribute.value.to_xes_value())
+        .unwrap_or_default()
+}
+
+/// Writes the names of the mapped columns, without ending the header record.
+fn write_mapped_header<W: io::Write>(wtr: &mut csv::Writer<W>, columns: &EventLogColumns) -> Result<()> {
+    wtr.write_field(&columns.case)?;
+    if let Some(variant) = &columns.variant {
+        wtr.write_field(variant)?;
+    }
+    wtr.write_field(&columns.activity)?;
+    if let Some(resource) = &columns.resource {
+        wtr.write_field(resource)?;
+    }
+    for column in columns.shape_columns() {
+        wtr.write_field(column)?;
+    }
+    Ok(())
+}
+
+/// Writes the case, variant, activity and resource fields of an event, skipping the columns the mapping lacks.
+fn write_mapped_fields<W: io::Write>(
+    wtr: &mut csv::Writer<W>,
+    columns: &EventLogColumns,
+    [case, variant, activity, resource]: [&str; 4],
+) -> Result<()> {
+    wtr.write_field(case)?;
+    if columns.variant.is_some() {
+        wtr.write_field(variant)?;
+    }
+    wtr.write_field(activity)?;
+    if columns.resource.is_some() {
+        wtr.write_field(resource)?;
+    }
+    Ok(())
+}
+
+/// Writes interval traces to CSV one at a time, so a log never has to be fully materialized.
+pub struct IntervalCsvWriter<W: io::Write> {
+    wtr: csv::Writer<W>,
+    columns: EventLogColumns,
+    details: DetailColumns,
+    attribute_columns: Vec<String>,
+    header_written: bool,
+}
+
+impl<W: io::Write> IntervalCsvWriter<W> {
+    /// Creates a writer with the given attribute columns after the mapped ones. Attributes not listed there
+    /// are not written.
+    pub fn new(writer: W, attribute_columns: Vec<String>) -> IntervalCsvWriter<W> {
+        IntervalCsvWriter::with_dialect(writer, attribute_columns, &CsvDialect::default())
+    }
+
+    pub fn with_dialect(writer: W, attribute_columns: Vec<String>, dialect: &CsvDialect) -> IntervalCsvWriter<W> {
+        IntervalCsvWriter {
+            wtr: dialect.writer(writer),
+            columns: EventLogColumns::default_style(),
+            details: DetailColumns::default(),
+            attribute_columns,
+            header_written: !dialect.has_header,
+        }
+    }
+
+    /// Names the mapped columns in the header. The shape of the columns is ignored. Traces are written without
+    /// variant or resource column if the columns have none.
+    pub fn with_columns(mut self, columns: EventLogColumns) -> IntervalCsvWriter<W> {
+        self.columns = EventLogColumns {
+            shape: CsvShape::Interval,
+            ..columns
+        };
+        self
+    }
+
+    /// Adds the columns of lifecycle details after the end time. Attributes of the same name are not written.
+    pub fn with_details(mut self, details: DetailColumns) -> IntervalCsvWriter<W> {
+        let names = details.names();
+        self.attribute_columns.retain(|key| !names.contains(&key.as_str()));
+        self.details = details;
+        self
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        write_mapped_header(&mut self.wtr, &self.columns)?;
+        for name in self.details.names() {
+            self.wtr.write_field(name)?;
+        }
+        let attribute_columns = self.attribute_columns.iter().map(|key| self.columns.attribute_column(key));
+        self.wtr.write_record(attribute_columns)?;
+        self.header_written = true;
+        Ok(())
+    }
+
+    pub fn write_trace(&mut self, trace: &interval::Trace) -> Result<()> {
+        if !self.header_written {
+            self.write_header()?;
+        }
+
+        for event in &trace.events {
+            let fields = [trace.case.as_str(), &trace.variant, &event.activity, &event.resource];
+            write_mapped_fields(&mut self.wtr, &self.columns, fields)?;
+            self.wtr.write_field(timestamp::format(&event.start_time))?;
+            self.wtr.write_field(timestamp::format(&event.end_time))?;
+            if self.details.completion {
+                self.wtr.write_field(event.completion.lifecycle_transition())?;
+            }
+            if self.details.scheduled_time {
+                self.wtr.write_field(event.scheduled_time.as_ref().map(timestamp::format).unwrap_or_default())?;
+            }
+            if self.details.suspensions {
+                self.wtr.write_field(interval::format_suspensions(&event.suspensions))?;
+                self.wtr.write_field(format_seconds(event.processing_time()))?;
+            }
+            for column in &self.attribute_columns {
+                self.wtr.write_field(attribute_value(column, &event.attributes, &trace.attributes))?;
+            }
+            self.wtr.write_record(None::<&[u8]>)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.wtr.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes lifecycle traces to CSV with one row per event and a single timestamp column, without pairing start and
+/// complete events.
+pub struct LifecycleCsvWriter<W: io::Write> {
+    wtr: csv::Writer<W>,
+    columns: EventLogColumns,
+    attribute_columns: Vec<String>,
+    header_written: bool,
+}
+
+impl<W: io::Write> LifecycleCsvWriter<W> {
+    /// Creates a writer for the lifecycle or atomic shape. The atomic shape has no lifecycle column and only
+    /// keeps `complete` events and events without a transition.
+    pub fn new(writer: W, attribute_columns: Vec<String>, shape: CsvShape) -> LifecycleCsvWriter<W> {
+        LifecycleCsvWriter::with_dialect(writer, attribute_columns, shape, &CsvDialect::default())
+    }
+
+    pub fn with_dialect(
+        writer: W,
+        attribute_columns: Vec<String>,
+        shape: CsvShape,
+        dialect: &CsvDialect,
+    ) -> LifecycleCsvWriter<W> {
+        LifecycleCsvWriter {
+            wtr: dialect.writer(writer),
+            columns: EventLogColumns {
+                shape,
+                ..EventLogColumns::default_style()
+            },
+            attribute_columns,
+            header_written: !dialect.has_header,
+        }
+    }
+
+    /// Names the mapped columns in the header. The shape given to the constructor is kept. Traces are written
+    /// without variant or resource column if the columns have none.
+    pub fn with_columns(mut self, columns: EventLogColumns) -> LifecycleCsvWriter<W> {
+        self.columns = EventLogColumns {
+            shape: self.columns.shape,
+            ..columns
+        };
+        self
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        write_mapped_header(&mut self.wtr, &self.columns)?;
+        let attribute_columns = self.attribute_columns.iter().map(|key| self.columns.attribute_column(key));
+        self.wtr.write_record(attribute_columns)?;
+        self.header_written = true;
+        Ok(())
+    }
+
+    pub fn write_trace(&mut self, trace: &lifecycle::Trace) -> Result<()> {
+        if !self.header_written {
+            self.write_header()?;
+        }
+
+        for event in &trace.events {
+            if self.columns.shape == CsvShape::Atomic && !event.is_complete() {
+                continue;
+            }
+            let fields = [trace.case.as_str(), &trace.variant, &event.activity, &event.resource];
+            write_mapped_fields(&mut self.wtr, &self.columns, fields)?;
+            self.wtr.write_field(event.timestamp.as_ref().map(timestamp::format).unwrap_or_default())?;
+            if self.columns.shape == CsvShape::Lifecycle {
+                self.wtr.write_field(&event.lifecycle)?;
+            }
+            for column in &self.attribute_columns {
+                self.wtr.write_field(attribute_value(column, &event.attributes, &trace.attributes))?;
+            }
+            self.wtr.write_record(None::<&[u8]>)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.wtr.flush()?;
+        Ok(())
+    }
+}
+
+pub fn csv_to_interval(bytes: &[u8], event_log: &mut EventLog, timestamps: &TimestampParser) -> Result<()> {
+    let schema = CsvSchema::infer(bytes, &event_log.columns, &CsvDialect::default())?;
+    event_log.traces = CsvTraceReader::new(bytes, &schema, timestamps.clone(), &ConversionOptions::default())?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(())
+}
+
+/// Reads the header of a CSV log, or the column positions starting at 1 if the dialect has no header.
+pub fn read_headers(reader: impl io::Read, dialect: &CsvDialect) -> Result<csv::StringRecord> {
+    headers(&mut dialect.reader(reader), dialect)
+}
+
+fn headers<R: io::Read>(reader: &mut csv::Reader<R>, dialect: &CsvDialect) -> Result<csv::StringRecord> {
+    Ok(if dialect.has_header {
+        reader.headers()?.clone()
+    } else {
+        (1..=reader.headers()?.len()).map(|position| position.to_string()).collect()
+    })
+}
+
+/// Rows per sorted run when a CSV log is not grouped by case. Runs are spilled to temporary files, so at most this
+/// many rows are held in memory.
+pub const SORT_RUN_ROWS: usize = 500_000;
+
+/// Header indices of the mapped columns and the types of the extra columns of a CSV log.
+#[derive(Debug, Clone)]
+pub struct CsvSchema {
+    case: usize,
+    variant: Option<usize>,
+    activity: usize,
+    resource: Option<usize>,
+    timestamps: TimestampColumns,
+
+    /// Indices of the columns of lifecycle details in the interval shape, see [`DetailColumns`].
+    completion: Option<usize>,
+    scheduled_time: Option<usize>,
+    suspensions: Option<usize>,
+    processing_time: Option<usize>,
+    extra_columns: Vec<ExtraColumn>,
+    headers: csv::StringRecord,
+    dialect: CsvDialect,
+
+    /// Whether the rows of each case are contiguous.
+    grouped: bool,
+}
+
+/// Indices of the timestamp and lifecycle columns of a CSV shape.
+#[derive(Debug, Clone)]
+enum TimestampColumns {
+    Interval { start_time: usize, end_time: usize },
+    Lifecycle { timestamp: usize, lifecycle: usize },
+    Atomic { timestamp: usize },
+}
+
+#[derive(Debug, Clone)]
+struct ExtraColumn {
+    index: usize,
+    name: String,
+    column_type: ColumnType,
+
+    /// Whether the value is the same for all events of every case, which makes the column a trace attribute.
+    is_trace_attribute: bool,
+}
+
+impl CsvSchema {
+    /// Finds the mapped columns in the header and infers the extra column types in a pass over all rows. The
+    /// rows are read one at a time, so this does not hold the log in memory.
+    pub fn infer(reader: impl io::Read, columns: &EventLogColumns, dialect: &CsvDialect) -> Result<CsvSchema> {
+        let mut reader = dialect.reader(reader);
+        let headers = headers(&mut reader, dialect)?;
+        let mut inference = SchemaInference::new(CsvSchema::map(&headers, columns, dialect)?);
+        let mut record = csv::StringRecord::new();
+        while reader.read_record(&mut record)? {
+            inference.add(&record);
+        }
+        Ok(inference.finish())
+    }
+
+    /// Finds the mapped columns in the header. The extra columns are strings until their types are inferred.
+    fn map(headers: &csv::StringRecord, columns: &EventLogColumns, dialect: &CsvDialect) -> Result<CsvSchema> {
+        let index = |column: &String| {
+            headers
+                .iter()
+                .position(|header| header == column)
+                .ok_or_else(|| Error::MissingColumn { column: column.clone() })
+        };
+        let detail_index = |column: &str| {
+            let index = headers.iter().position(|header| header == column);
+            index.filter(|_| columns.shape == CsvShape::Interval)
+        };
+        let mut schema = CsvSchema {
+            case: index(&columns.case)?,
+            variant: columns.variant.as_ref().map(index).transpose()?,
+            activity: index(&columns.activity)?,
+            resource: columns.resource.as_ref().map(index).transpose()?,
+            timestamps: match columns.shape {
+                CsvShape::Interval => TimestampColumns::Interval {
+                    start_time: index(&columns.start_time)?,
+                    end_time: index(&columns.end_time)?,
+                },
+                CsvShape::Lifecycle => TimestampColumns::Lifecycle {
+                    timestamp: index(&columns.timestamp)?,
+                    lifecycle: index(&columns.lifecycle)?,
+                },
+                CsvShape::Atomic => TimestampColumns::Atomic {
+                    timestamp: index(&columns.timestamp)?,
+                },
+            },
+            completion: detail_index(COMPLETION_COLUMN),
+            scheduled_time: detail_index(SCHEDULED_TIME_COLUMN),
+            suspensions: detail_index(SUSPENSIONS_COLUMN),
+            processing_time: detail_index(PROCESSING_TIME_COLUMN),
+            extra_columns: Vec::new(),
+            headers: headers.clone(),
+            dialect: dialect.clone(),
+            grouped: true,
+        };
+        let (first_time, second_time) = match schema.timestamps {
+            TimestampColumns::Interval { start_time, end_time } => (start_time, Some(end_time)),
+            TimestampColumns::Lifecycle { timestamp, lifecycle } => (timestamp, Some(lifecycle)),
+            TimestampColumns::Atomic { timestamp } => (timestamp, None),
+        };
+        let mapped_indices = [
+            Some(schema.case),
+            schema.variant,
+            Some(schema.activity),
+            schema.resource,
+            Some(first_time),
+            second_time,
+            schema.completion,
+            schema.scheduled_time,
+            schema.suspensions,
+            schema.processing_time,
+        ];
+        schema.extra_columns = (0..headers.len())
+            .filter(|index| !mapped_indices.contains(&Some(*index)))
+            .map(|index| ExtraColumn {
+                index,
+                name: columns.attribute_key(&headers[index]).to_string(),
+                column_type: ColumnType::String,
+                is_trace_attribute: true,
+            })
+            .collect();
+        Ok(schema)
+    }
+}
+
+/// Infers the types of the extra columns, whether they are trace attributes and whether the rows are grouped by
+/// case, one row at a time.
+struct SchemaInference {
+    schema: CsvSchema,
+    column_types: Vec<Option<ColumnType>>,
+    constant_columns: ConstantColumns,
+    cases: HashSet<String>,
+}
+
+impl SchemaInference {
+    fn new(schema: CsvSchema) -> SchemaInference {
+        SchemaInference {
+            column_types: vec![None; schema.extra_columns.len()],
+            constant_columns: ConstantColumns::new(&schema),
+            cases: HashSet::new(),
+            schema,
+        }
+    }
+
+    fn add(&mut self, record: &csv::StringRecord) {
+        if self.constant_columns.add(record) && !self.cases.insert(record[self.schema.case].to_string()) {
+            self.schema.grouped = false;
+        }
+        for (column_type, column) in self.column_types.iter_mut().zip(&self.schema.extra_columns) {
+            let value = &record[column.index];
+            if !value.is_empty() {
+                let value_type = ColumnType::of(value);
+                *column_type = Some(column_type.map_or(value_type, |t| t.widen(value_type)));
+            }
+        }
+    }
+
+    fn finish(mut self) -> CsvSchema {
+        for (column, column_type) in self.schema.extra_columns.iter_mut().zip(self.column_types) {
+            column.column_type = column_type.unwrap_or(ColumnType::String);
+        }
+        self.constant_columns.apply(&mut self.schema);
+        self.schema
+    }
+}
+
+/// Tracks which extra columns have the same value in all rows of each case, for rows grouped by case.
+struct ConstantColumns {
+    case: usize,
+    case_record: csv::StringRecord,
+    constant: Vec<(usize, bool)>,
+}
+
+impl ConstantColumns {
+    fn new(schema: &CsvSchema) -> ConstantColumns {
+        ConstantColumns {
+            case: schema.case,
+            case_record: csv::StringRecord::new(),
+            constant: schema.extra_columns.iter().map(|column| (column.index, true)).collect(),
+        }
+    }
+
+    /// Adds the next row and returns whether it starts a new run of rows of a case.
+    fn add(&mut self, record: &csv::StringRecord) -> bool {
+        let new_case = self.case_record.is_empty() || record[self.case] != self.case_record[self.case];
+        if new_case {
+            self.case_record.clone_from(record);
+        }
+        for (index, constant) in &mut self.constant {
+            *constant &= record[*index] == self.case_record[*index];
+        }
+        new_case
+    }
+
+    fn apply(self, schema: &mut CsvSchema) {
+        for (column, (_, constant)) in schema.extra_columns.iter_mut().zip(self.constant) {
+            column.is_trace_attribute = constant;
+        }
+    }
+}
+
+/// Rows of a CSV log in case order.
+enum Rows<R: io::Read> {
+    /// The input, which is already grouped by case.
+    Grouped(Box<csv::Reader<DecodeReaderBytes<R, Vec<u8>>>>),
+
+    /// The input sorted by case in runs on disk.
+    Sorted(MergedRuns),
+}
+
+impl<R: io::Read> Rows<R> {
+    fn read_record(&mut self, record: &mut csv::StringRecord) -> Result<bool> {
+        match self {
+            Rows::Grouped(reader) => Ok(reader.read_record(record)?),
+            Rows::Sorted(runs) => runs.read_record(record),
+        }
+    }
+}
+
+/// Sorts the rows of a CSV log by case in runs of `run_rows` rows, each spilled to a temporary file. Cases are in
+/// order of first appearance and rows keep their order within a case. Each spilled row starts with the case order
+/// and the line in the input, so that errors still point to the original line. `inspect` sees every row as it is
+/// read.
+///
+/// Rows are written to the first run as they are read for as long as they are grouped by case, so input that is
+/// grouped ends up in a single run without being held in memory.
+fn sort_runs<R: io::Read>(
+    reader: &mut csv::Reader<R>,
+    case: usize,
+    run_rows: usize,
+    mut inspect: impl FnMut(&csv::StringRecord),
+) -> Result<Vec<File>> {
+    let mut case_order = HashMap::new();
+    let mut files = Vec::new();
+    let mut first_run = Some(csv::Writer::from_writer(tempfile::tempfile()?));
+    let mut run = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        inspect(&record);
+        let order = match case_order.get(&record[case]) {
+            Some(order) => *order,
+            None => {
+                let order = case_order.len() as u64;
+                case_order.insert(record[case].to_string(), order);
+                order
+            }
+        };
+        let line = record.position().map_or(0, |p| p.line());
+        if let Some(writer) = &mut first_run {
+            // Cases are numbered in order of first appearance, so grouped rows never go back to an earlier case
+            if order + 1 >= case_order.len() as u64 {
+                write_spilled(writer, order, line, &record)?;
+                continue;
+            }
+            files.push(finish_run(first_run.take().unwrap())?);
+        }
+        run.push((order, line, record));
+        if run.len() >= run_rows {
+            files.push(spill_run(&mut run)?);
+        }
+    }
+    if let Some(writer) = first_run {
+        files.push(finish_run(writer)?);
+    }
+    if !run.is_empty() {
+        files.push(spill_run(&mut run)?);
+    }
+    Ok(files)
+}
+
+fn spill_run(run: &mut Vec<(u64, u64, csv::StringRecord)>) -> Result<File> {
+    run.sort_by_key(|(order, line, _)| (*order, *line));
+
+    let mut writer = csv::Writer::from_writer(tempfile::tempfile()?);
+    for (order, line, record) in run.drain(..) {
+        write_spilled(&mut writer, order, line, &record)?;
+    }
+    finish_run(writer)
+}
+
+fn write_spilled(writer: &mut csv::Writer<File>, order: u64, line: u64, record: &csv::StringRecord) -> Result<()> {
+    writer.write_field(order.to_string())?;
+    writer.write_field(line.to_string())?;
+    writer.write_record(record)?;
+    Ok(())
+}
+
+fn finish_run(mut writer: csv::Writer<File>) -> Result<File> {
+    writer.flush()?;
+    let error = |e: csv::IntoInnerError<_>| Error::Io(io::Error::new(e.error().kind(), e.error().to_string()));
+    writer.into_inner().map_err(error)
+}
+
+/// Merges sorted runs into one sequence of rows in case order.
+struct MergedRuns {
+    readers: Vec<csv::Reader<BufReader<File>>>,
+
+    /// The next spilled row of each run.
+    rows: Vec<csv::StringRecord>,
+
+    /// Runs by the case order of their next row. Ties go to the earlier run, which has the earlier rows.
+    heads: BinaryHeap<Reverse<(u64, usize)>>,
+}
+
+impl MergedRuns {
+    /// Starts a merge from the beginning of the run files.
+    fn new(files: &[File]) -> Result<MergedRuns> {
+        let mut merged = MergedRuns {
+            readers: Vec::new(),
+            rows: Vec::new(),
+            heads: BinaryHeap::new(),
+        };
+        for (run, file) in files.iter().enumerate() {
+            let mut file = file.try_clone()?;
+            file.rewind()?;
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .from_reader(BufReader::new(file));
+            let mut row = csv::StringRecord::new();
+            if reader.read_record(&mut row)? {
+                merged.heads.push(Reverse((spilled_number(&row, 0), run)));
+            }
+            merged.readers.push(reader);
+            merged.rows.push(row);
+        }
+        Ok(merged)
+    }
+
+    fn read_record(&mut self, record: &mut csv::StringRecord) -> Result<bool> {
+        let Some(Reverse((_, run))) = self.heads.pop() else {
+            return Ok(false);
+        };
+
+        let row = &self.rows[run];
+        record.clear();
+        for field in row.iter().skip(2) {
+            record.push_field(field);
+        }
+        let mut position = csv::Position::new();
+        position.set_line(spilled_number(row, 1));
+        record.set_position(Some(position));
+
+        if self.readers[run].read_record(&mut self.rows[run])? {
+            self.heads.push(Reverse((spilled_number(&self.rows[run], 0), run)));
+        }
+        Ok(true)
+    }
+}
+
+fn spilled_number(row: &csv::StringRecord, index: usize) -> u64 {
+    row[index].parse().expect("spilled rows start with the case order and line")
+}
+
+/// Streaming CSV reader that yields one interval trace per case, with events ordered by start time.
+///
+/// Input that is grouped by case is read as it is. Otherwise it is first sorted by case on disk, see
+/// [`SORT_RUN_ROWS`].
+pub struct CsvTraceReader<R: io::Read> {
+    rows: Rows<R>,
+    schema: CsvSchema,
+    timestamps: TimestampParser,
+
+    /// How the rows of the lifecycle shape are paired into intervals.
+    conversion: ConversionOptions,
+
+    /// The current row. Holds the first row of the next trace when `peeked` is set.
+    record: csv::StringRecord,
+    peeked: bool,
+    done: bool,
+}
+
+impl<R: io::Read> CsvTraceReader<R> {
+    /// Creates a reader for a CSV log whose header matches the one `schema` was inferred from. Rows of the
+    /// lifecycle shape are paired into intervals with the `conversion` options.
+    pub fn new(
+        reader: R,
+        schema: &CsvSchema,
+        timestamps: TimestampParser,
+        conversion: &ConversionOptions,
+    ) -> Result<CsvTraceReader<R>> {
+        CsvTraceReader::with_sort_run_rows(reader, schema, timestamps, conversion, SORT_RUN_ROWS)
+    }
+
+    /// Like [`CsvTraceReader::new`] with the number of rows per sorted run, if the input is not grouped by case.
+    pub fn with_sort_run_rows(
+        reader: R,
+        schema: &CsvSchema,
+        timestamps: TimestampParser,
+        conversion: &ConversionOptions,
+        sort_run_rows: usize,
+    ) -> Result<CsvTraceReader<R>> {
+        let mut reader = schema.dialect.reader(reader);
+        reader.headers()?;
+
+        if !schema.grouped {
+            let runs = sort_runs(&mut reader, schema.case, sort_run_rows.max(1), |_| ())?;
+            return CsvTraceReader::from_runs(&runs, schema.clone(), timestamps, conversion);
+        }
+        Ok(CsvTraceReader::from_rows(
+            Rows::Grouped(Box::new(reader)),
+            schema.clone(),
+            timestamps,
+            conversion,
+        ))
+    }
+
+    /// Reads a CSV log in a single pass over `reader`, for input that cannot be read again. The mapping is resolved
+    /// from the header by `columns`, and the extra column types are inferred while the rows are sorted by case into
+    /// runs on disk, which are read back instead of the input. Rows that are grouped by case are written to a single
+    /// run as they are read, see [`SORT_RUN_ROWS`] for the others.
+    pub fn single_pass(
+        reader: R,
+        dialect: &CsvDialect,
+        columns: impl FnOnce(&csv::StringRecord) -> EventLogColumns,
+        timestamps: TimestampParser,
+        conversion: &ConversionOptions,
+    ) -> Result<CsvTraceReader<R>> {
+        let mut reader = dialect.reader(reader);
+        let headers = headers(&mut reader, dialect)?;
+        let schema = CsvSchema::map(&headers, &columns(&headers), dialect)?;
+        let case = schema.case;
+        let mut inference = SchemaInference::new(schema);
+        let runs = sort_runs(&mut reader, case, SORT_RUN_ROWS, |record| inference.add(record))?;
+        CsvTraceReader::from_runs(&runs, inference.finish(), timestamps, conversion)
+    }
+
+    /// Reads the rows in case order from sorted runs.
+    fn from_runs(
+        runs: &[File],
+        mut schema: CsvSchema,
+        timestamps: TimestampParser,
+        conversion: &ConversionOptions,
+    ) -> Result<CsvTraceReader<R>> {
+        if !schema.grouped {
+            // Trace attributes were inferred from runs of rows, which are only whole cases once sorted
+            let mut constant_columns = ConstantColumns::new(&schema);
+            let mut merged = MergedRuns::new(runs)?;
+            let mut record = csv::StringRecord::new();
+            while merged.read_record(&mut record)? {
+                constant_columns.add(&record);
+            }
+            constant_columns.apply(&mut schema);
+        }
+        let rows = Rows::Sorted(MergedRuns::new(runs)?);
+        Ok(CsvTraceReader::from_rows(rows, schema, timestamps, conversion))
+    }
+
+    fn from_rows(
+        rows: Rows<R>,
+        schema: CsvSchema,
+        timestamps: TimestampParser,
+        conversion: &ConversionOptions,
+    ) -> CsvTraceReader<R> {
+        CsvTraceReader {
+            rows,
+            schema,
+            timestamps,
+            conversion: conversion.clone(),
+            record: csv::StringRecord::new(),
+            peeked: false,
+            done: false,
+        }
+    }
+
+    /// Yields lifecycle traces instead, with one event per row, or a start and a complete event per row of the
+    /// interval shape.
+    pub fn lifecycle_traces(self) -> CsvLifecycleTraces<R> {
+        CsvLifecycleTraces(self)
+    }
+
+    /// Reads the rows of the next case. `add_events` adds the events of the current row.
+    fn next_case<E>(&mut self, add_events: fn(&Self, &mut Vec<E>) -> Result<()>) -> Result<Option<CaseRows<E>>> {
+        if !self.peeked && !self.rows.read_record(&mut self.record)? {
+            return Ok(None);
+        }
+        self.peeked = false;
+
+        let record = &self.record;
+        let mut case = CaseRows {
+            case: record[self.schema.case].to_string(),
+            variant: self.schema.variant.map(|index| record[index].to_string()).unwrap_or_default(),
+            attributes: self.attributes(true),
+            events: Vec::new(),
+        };
+        loop {
+            add_events(self, &mut case.events)?;
+            if !self.rows.read_record(&mut self.record)? {
+                break;
+            }
+            if self.record[self.schema.case] != case.case {
+                self.peeked = true;
+                break;
+            }
+        }
+        Ok(Some(case))
+    }
+
+    fn next_trace(&mut self) -> Result<Option<Trace>> {
+        if let TimestampColumns::Lifecycle { .. } = self.schema.timestamps {
+            // Rows are lifecycle transitions, which are paired like the events of an XES log
+            return self
+                .next_lifecycle_trace()?
+                .map(|trace| conversion::lifecycle_trace_to_interval(&trace, &self.conversion))
+                .transpose();
+        }
+
+        let Some(case) = self.next_case(|reader, events| {
+            events.push(reader.interval_event()?);
+            Ok(())
+        })?
+        else {
+            return Ok(None);
+        };
+        let mut trace = Trace {
+            case: case.case,
+            variant: case.variant,
+            events: case.events,
+            attributes: case.attributes,
+        };
+        trace.events.sort_by_key(|event| event.start_time);
+        Ok(Some(trace))
+    }
+
+    fn next_lifecycle_trace(&mut self) -> Result<Option<lifecycle::Trace>> {
+        let Some(case) = self.next_case(Self::lifecycle_events)? else {
+            return Ok(None);
+        };
+        let mut trace = lifecycle::Trace {
+            case: case.case,
+            variant: case.variant,
+            events: case.events,
+            attributes: case.attributes,
+        };
+        trace.events.sort_by_key(|event| event.timestamp);
+        Ok(Some(trace))
+    }
+
+    fn parse_timestamp(&self, index: usize) -> Result<Timestamp> {
+        let line = self.record.position().map_or(0, |p| p.line());
+        let column = &self.schema.headers[index];
+        self.timestamps
+            .parse(&self.record[index], || format!("line {line}, column '{column}'"))
+    }
+
+    fn resource(&self) -> String {
+        self.schema.resource.map(|index| self.record[index].to_string()).unwrap_or_default()
+    }
+
+    fn interval_event(&self) -> Result<interval::Event> {
+        let (start_time, end_time) = match self.schema.timestamps {
+            TimestampColumns::Interval { start_time, end_time } => {
+                (self.parse_timestamp(start_time)?, self.parse_timestamp(end_time)?)
+            }
+            TimestampColumns::Atomic { timestamp } | TimestampColumns::Lifecycle { timestamp, .. } => {
+                let timestamp = self.parse_timestamp(timestamp)?;
+                (timestamp, timestamp)
+            }
+        };
+
+        let mut event = interval::Event {
+            activity: self.record[self.schema.activity].to_string(),
+            resource: self.resource(),
+            start_time,
+            end_time,
+            attributes: self.attributes(false),
+            ..Default::default()
+        };
+        if let Some(index) = self.schema.completion {
+            event.completion = Completion::from_lifecycle_transition(&self.record[index])
+                .ok_or_else(|| self.invalid_value(index))?;
+        }
+        if let Some(index) = self.schema.scheduled_time.filter(|&index| !self.record[index].is_empty()) {
+            event.scheduled_time = Some(self.parse_timestamp(index)?);
+        }
+        if let Some(index) = self.schema.suspensions {
+            event.suspensions = event
+                .parse_suspensions(&self.record[index])
+                .ok_or_else(|| self.invalid_value(index))?;
+        }
+        if let Some(index) = self.schema.processing_time.filter(|&index| !self.record[index].is_empty()) {
+            let seconds = self.record[index].parse::<f64>().ok();
+            let seconds = seconds
+                .filter(|&seconds| event.checked_processing_time(seconds).is_some())
+                .ok_or_else(|| self.invalid_value(index))?;
+            if self.schema.suspensions.is_none() {
+                let attribute = Attribute::new(PROCESSING_TIME_COLUMN, AttributeValue::Float(seconds));
+                event.attributes.insert(attribute);
+            }
+        }
+        Ok(event)
+    }
+
+    /// The error for a value of a column of lifecycle details that does not parse or is out of range.
+    fn invalid_value(&self, index: usize) -> Error {
+        let line = self.record.position().map_or(0, |p| p.line());
+        Error::InvalidAttribute {
+            key: self.schema.headers[index].to_string(),
+            value: self.record[index].to_string(),
+            location: format!("line {line}"),
+        }
+    }
+
+    fn lifecycle_events(&self, events: &mut Vec<lifecycle::Event>) -> Result<()> {
+        let event = |lifecycle: &str, timestamp: Timestamp| lifecycle::Event {
+            activity: self.record[self.schema.activity].to_string(),
+            resource: self.resource(),
+            timestamp: Some(timestamp),
+            lifecycle: lifecycle.to_string(),
+            attributes: self.attributes(false),
+        };
+
+        match self.schema.timestamps {
+            TimestampColumns::Interval { start_time, end_time } => {
+                events.push(event("start", self.parse_timestamp(start_time)?));
+                events.push(event("complete", self.parse_timestamp(end_time)?));
+            }
+            TimestampColumns::Lifecycle { timestamp, lifecycle } => {
+                events.push(event(&self.record[lifecycle], self.parse_timestamp(timestamp)?));
+            }
+            TimestampColumns::Atomic { timestamp } => {
+                events.push(event("complete", self.parse_timestamp(timestamp)?));
+            }
+        }
+        Ok(())
+    }
+
+    /// The trace or event attributes of the extra columns of the current row.
+    fn attributes(&self, trace_attributes: bool) -> Attributes {
+        self.schema
+            .extra_columns
+            .iter()
+            .filter(|column| column.is_trace_attribute == trace_attributes && !self.record[column.index].is_empty())
+            .map(|column| column.column_type.attribute(&column.name, &self.record[column.index]))
+            .collect()
+    }
+}
+
+/// The rows of one case, read into events of type `E`.
+struct CaseRows<E> {
+    case: String,
+    variant: String,
+    attributes: Attributes,
+    events: Vec<E>,
+}
+
+impl<R: io::Read> Iterator for CsvTraceReader<R> {
+    type Item = Result<Trace>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_trace();
+        iterator_item(&mut self.done, next)
+    }
 }
 
-impl EventLogColumns {
-    pub fn default_style() -> EventLogColumns {
-        EventLogColumns {
-            case: "case".to_string(),
-            variant: "variant".to_string(),
-            activity: "activity".to_string(),
-            resource: "resource".to_string(),
-            start_time: "start_time".to_string(),
-            end_time: "end_time".to_string(),
+/// Streaming CSV reader that yields lifecycle traces, see [`CsvTraceReader::lifecycle_traces`].
+pub struct CsvLifecycleTraces<R: io::Read>(CsvTraceReader<R>);
+
+impl<R: io::Read> Iterator for CsvLifecycleTraces<R> {
+    type Item = Result<lifecycle::Trace>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.done {
+            return None;
         }
+        let next = self.0.next_lifecycle_trace();
+        iterator_item(&mut self.0.done, next)
     }
 }
 
-pub fn interval_to_csv(event_log: &interval::EventLog, writer: &mut impl io::Write) {
-    let mut wtr = csv::Writer::from_writer(writer);
-    for trace in &event_log.traces {
-        for event in &trace.events {
-            wtr.serialize(EventLogColumns {
-                case: trace.case.to_string(),
-                variant: trace.variant.to_string(),
-                activity: event.activity.to_string(),
-                resource: event.resource.to_string(),
-                start_time: event.start_time.to_string(),
-                end_time: event.end_time.to_string(),
-            })
-            .unwrap();
+/// Turns the result of reading the next item into an iterator item. Iteration ends at the end of the input or
+/// after the first error.
+pub(crate) fn iterator_item<T>(done: &mut bool, next: Result<Option<T>>) -> Option<Result<T>> {
+    match next {
+        Ok(Some(item)) => Some(Ok(item)),
+        Ok(None) => {
+            *done = true;
+            None
+        }
+        Err(e) => {
+            *done = true;
+            Some(Err(e))
         }
     }
-    wtr.flush().unwrap();
 }
 
-pub fn csv_to_interval(bytes: &[u8], event_log: &mut EventLog) {
-    let mut reader = csv::Reader::from_reader(bytes);
+/// The narrowest XES attribute type that all non-empty values of an extra CSV column parse as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColumnType {
+    Int,
+    Float,
+    Boolean,
+    String,
+}
 
-    let rows = reader
-        .deserialize()
-        .map(|row| row.expect("Could not deserialize row"))
-        .collect::<Vec<HashMap<String, String>>>();
+impl ColumnType {
+    /// The type of a single non-empty value.
+    fn of(value: &str) -> ColumnType {
+        // Numbers with leading zeros are not typed, so that values like "007" are written back unchanged
+        let digits = value.trim_start_matches(['+', '-']);
+        let leading_zero = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+        if value.parse::<i64>().is_ok_and(|v| v.to_string() == value) {
+            ColumnType::Int
+        } else if !leading_zero && value.parse::<f64>().is_ok_and(f64::is_finite) {
+            ColumnType::Float
+        } else if value == "true" || value == "false" {
+            ColumnType::Boolean
+        } else {
+            ColumnType::String
+        }
+    }
 
-    let traces = rows
-        .group_by(|a, b| a.get(&event_log.columns.case).unwrap() == b.get(&event_log.columns.case).unwrap())
-        .map(|group| {
-            let events = group
-                .iter()
-                .map(|row| interval::Event {
-                    activity: row.get(&event_log.columns.activity).unwrap().to_string(),
-                    resource: row.get(&event_log.columns.resource).unwrap().to_string(),
-                    start_time: row.get(&event_log.columns.start_time).unwrap().to_string(),
-                    end_time: row.get(&event_log.columns.end_time).unwrap().to_string(),
-                })
-                .collect::<Vec<_>>();
-
-            Trace {
-                case: group[0].get(&event_log.columns.case).unwrap().to_string(),
-                variant: group[0].get(&event_log.columns.variant).unwrap().to_string(),
-                events,
+    /// The narrowest type that values of both types parse as.
+    fn widen(self, other: ColumnType) -> ColumnType {
+        match (self, other) {
+            (ColumnType::Int, ColumnType::Float) | (ColumnType::Float, ColumnType::Int) => ColumnType::Float,
+            (column_type, other) if column_type == other => column_type,
+            _ => ColumnType::String,
+        }
+    }
+
+    /// Builds the attribute for a value that is known to parse as this type.
+    fn attribute(&self, key: &str, value: &str) -> Attribute {
+        let value = match self {
+            ColumnType::Int => AttributeValue::Int(value.parse().unwrap()),
+            ColumnType::Float => AttributeValue::Float(value.parse().unwrap()),
+            ColumnType::Boolean => AttributeValue::Boolean(value == "true"),
+            ColumnType::String => AttributeValue::String(value.to_string()),
+        };
+        Attribute::new(key, value)
+    }
+}
+
+pub fn lifecycle_to_xes(event_log: &lifecycle::EventLog, writer: &mut impl io::Write) -> Result<()> {
+    let mut xes_writer = XesWriter::new(writer, &event_log.header)?;
+    for trace in &event_log.traces {
+        xes_writer.write_trace(trace)?;
+    }
+    xes_writer.finish()?;
+    Ok(())
+}
+
+/// Writes a log to an XES file, compressed with gzip or Zstandard if the path ends with `.gz` or `.zst`.
+pub fn lifecycle_to_xes_file(event_log: &lifecycle::EventLog, path: &Path) -> Result<()> {
+    let file = io::BufWriter::new(File::create(path)?);
+    let mut writer = Compression::of_path(path).encoder(file)?;
+    lifecycle_to_xes(event_log, &mut writer)?;
+    writer.finish()?.flush()?;
+    Ok(())
+}
+
+/// Writes lifecycle traces to XES one at a time, so a log never has to be fully materialized.
+///
+/// Attributes are written as the element of their type, e.g., `<int>` or `<list>`. Strings and IDs without a value
+/// are left out, including an empty variant, resource or lifecycle transition.
+pub struct XesWriter<W: io::Write> {
+    writer: quick_xml::Writer<W>,
+}
+
+impl<W: io::Write> XesWriter<W> {
+    /// Writes the XML declaration, the opening `log` element and the log header.
+    pub fn new(writer: W, header: &LogHeader) -> Result<XesWriter<W>> {
+        XesWriter::start(quick_xml::Writer::new(writer), header)
+    }
+
+    /// Like [`XesWriter::new`], but puts every element on its own line, indented by `indent` spaces per level.
+    pub fn indented(writer: W, header: &LogHeader, indent: usize) -> Result<XesWriter<W>> {
+        XesWriter::start(quick_xml::Writer::new_with_indent(writer, b' ', indent), header)
+    }
+
+    fn start(mut writer: quick_xml::Writer<W>, header: &LogHeader) -> Result<XesWriter<W>> {
+        let xml_declaration = BytesDecl::new("1.0", Some("UTF-8"), None);
+        writer.write_event(XmlEvent::Decl(xml_declaration))?;
+
+        let mut log_element = BytesStart::new("log");
+        log_element.push_attribute(("xes.version", header.version.as_deref().unwrap_or("1.0")));
+        if let Some(features) = header.features.as_deref() {
+            log_element.push_attribute(("xes.features", features));
+        }
+        writer.write_event(XmlEvent::Start(log_element))?;
+        header.write(&mut writer)?;
+
+        Ok(XesWriter { writer })
+    }
+
+    pub fn write_trace(&mut self, trace: &lifecycle::Trace) -> Result<()> {
+        self.writer.create_element("trace").write_inner_content(|w| {
+            write_string(w, "concept:name", &trace.case)?;
+            write_string(w, "variant", &trace.variant)?;
+            write_attributes(w, &trace.attributes)?;
+            for event in &trace.events {
+                w.create_element("event").write_inner_content(|w| {
+                    write_string(w, "concept:name", &event.activity)?;
+                    write_string(w, "org:resource", &event.resource)?;
+                    if let Some(timestamp) = &event.timestamp {
+                        w.create_element("date")
+                            .with_attribute(("key", "time:timestamp"))
+                            .with_attribute(("value", timestamp::format(timestamp).as_str()))
+                            .write_empty()?;
+                    }
+                    write_string(w, "lifecycle:transition", &event.lifecycle)?;
+                    write_attributes(w, &event.attributes)
+                })?;
             }
-        })
-        .collect::<Vec<_>>();
-
-    event_log.traces = traces;
-}
-
-pub fn lifecycle_to_xes(event_log: &lifecycle::EventLog, writer: &mut impl io::Write) {
-    let mut xml_writer = quick_xml::Writer::new(writer);
-
-    let xml_declaration = BytesDecl::new("1.0", Some("UTF-8"), None);
-    xml_writer.write_event(XmlEvent::Decl(xml_declaration)).unwrap();
-
-    xml_writer
-        .create_element("log")
-        .write_inner_content(|w| {
-            event_log.traces.iter().for_each(|trace| {
-                w.create_element("trace")
-                    .write_inner_content(|w| {
-                        w.create_element("string")
-                            .with_attribute(("key", "concept:name"))
-                            .with_attribute(("value", trace.case.as_str()))
-                            .write_empty()
-                            .unwrap();
-                        w.create_element("string")
-                            .with_attribute(("key", "variant"))
-                            .with_attribute(("value", trace.variant.as_str()))
-                            .write_empty()
-                            .unwrap();
-                        trace.events.iter().for_each(|event| {
-                            w.create_element("event")
-                                .write_inner_content(|w| {
-                                    w.create_element("string")
-                                        .with_attribute(("key", "concept:name"))
-                                        .with_attribute(("value", event.activity.as_str()))
-                                        .write_empty()
-                                        .unwrap();
-                                    w.create_element("string")
-                                        .with_attribute(("key", "org:resource"))
-                                        .with_attribute(("value", event.resource.as_str()))
-                                        .write_empty()
-                                        .unwrap();
-                                    w.create_element("date")
-                                        .with_attribute(("key", "time:timestamp"))
-                                        .with_attribute(("value", event.timestamp.as_str()))
-                                        .write_empty()
-                                        .unwrap();
-                                    w.create_element("string")
-                                        .with_attribute(("key", "lifecycle:transition"))
-                                        .with_attribute(("value", event.lifecycle.as_str()))
-                                        .write_empty()
-                                        .unwrap();
-                                    Ok(())
-                                })
-                                .unwrap();
-                        });
-                        Ok(())
-                    })
-                    .unwrap();
-            });
             Ok(())
-        })
-        .unwrap();
+        })?;
+        Ok(())
+    }
+
+    /// Closes the `log` element and returns the underlying writer, which still has to be flushed.
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.write_event(XmlEvent::End(BytesEnd::new("log")))?;
+        Ok(self.writer.into_inner())
+    }
+}
+
+/// Writes a `string` attribute unless the value is empty.
+fn write_string<W: io::Write>(writer: &mut quick_xml::Writer<W>, key: &str, value: &str) -> quick_xml::Result<()> {
+    if !value.is_empty() {
+        writer
+            .create_element("string")
+            .with_attribute(("key", key))
+            .with_attribute(("value", value))
+            .write_empty()?;
+    }
+    Ok(())
+}
+
+fn write_attributes<W: io::Write>(writer: &mut quick_xml::Writer<W>, attributes: &Attributes) -> quick_xml::Result<()> {
+    for attribute in attributes.iter().filter(|attribute| !attribute.value.is_empty()) {
+        write_attribute(writer, attribute)?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
     use crate::conversion;
+    use crate::timestamp::TimestampFormat;
+    use crate::xes::header::{Extension, LogHeader};
     use crate::xes::lifecycle;
 
     use super::*;
@@ -153,8 +1437,8 @@ mod tests {
         input_path.push("event_logs");
         input_path.push("Production.xes");
 
-        let mut log = lifecycle::parse_file(Path::new(&input_path), true);
-        let event_log = conversion::lifecycle_to_interval(&mut log);
+        let log = lifecycle::parse_file(Path::new(&input_path), true).unwrap();
+        let event_log = conversion::lifecycle_to_interval(&log, &Default::default()).unwrap();
 
         let mut output_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         output_path.push("test_output");
@@ -162,7 +1446,7 @@ mod tests {
 
         let mut csv_file = std::fs::File::create(output_path.clone()).unwrap();
 
-        interval_to_csv(&event_log, &mut csv_file);
+        interval_to_csv(&event_log, &mut csv_file).unwrap();
 
         assert!(output_path.exists());
 
@@ -179,7 +1463,7 @@ mod tests {
             columns: EventLogColumns::default_style(),
         };
 
-        csv_to_interval(data.as_bytes(), &mut event_log);
+        csv_to_interval(data.as_bytes(), &mut event_log, &TimestampParser::new(vec![TimestampFormat::EpochSeconds])).unwrap();
 
         assert_eq!(event_log.traces.len(), 1);
         assert_eq!(event_log.traces[0].events.len(), 1);
@@ -187,8 +1471,14 @@ mod tests {
         assert_eq!(event_log.traces[0].variant, "1");
         assert_eq!(event_log.traces[0].events[0].activity, "A");
         assert_eq!(event_log.traces[0].events[0].resource, "R1");
-        assert_eq!(event_log.traces[0].events[0].start_time, "1");
-        assert_eq!(event_log.traces[0].events[0].end_time, "2");
+        assert_eq!(
+            timestamp::format(&event_log.traces[0].events[0].start_time),
+            "1970-01-01T00:00:01.000+00:00"
+        );
+        assert_eq!(
+            timestamp::format(&event_log.traces[0].events[0].end_time),
+            "1970-01-01T00:00:02.000+00:00"
+        );
     }
 
     #[test]
@@ -200,15 +1490,16 @@ mod tests {
             traces: Vec::new(),
             columns: EventLogColumns {
                 case: "case_id".to_string(),
-                variant: "variant".to_string(),
+                variant: Some("variant".to_string()),
                 activity: "Activity".to_string(),
-                resource: "Resource".to_string(),
+                resource: Some("Resource".to_string()),
                 start_time: "start_timestamp".to_string(),
                 end_time: "end_timestamp".to_string(),
+                ..EventLogColumns::default_style()
             },
         };
 
-        csv_to_interval(data.as_bytes(), &mut event_log);
+        csv_to_interval(data.as_bytes(), &mut event_log, &TimestampParser::new(vec![TimestampFormat::EpochSeconds])).unwrap();
 
         assert_eq!(event_log.traces.len(), 1);
         assert_eq!(event_log.traces[0].events.len(), 1);
@@ -216,8 +1507,14 @@ mod tests {
         assert_eq!(event_log.traces[0].variant, "1");
         assert_eq!(event_log.traces[0].events[0].activity, "A");
         assert_eq!(event_log.traces[0].events[0].resource, "R1");
-        assert_eq!(event_log.traces[0].events[0].start_time, "1");
-        assert_eq!(event_log.traces[0].events[0].end_time, "2");
+        assert_eq!(
+            timestamp::format(&event_log.traces[0].events[0].start_time),
+            "1970-01-01T00:00:01.000+00:00"
+        );
+        assert_eq!(
+            timestamp::format(&event_log.traces[0].events[0].end_time),
+            "1970-01-01T00:00:02.000+00:00"
+        );
     }
 
     #[test]
@@ -232,7 +1529,7 @@ mod tests {
         };
         let bytes = std::fs::read(input_log).unwrap();
 
-        csv_to_interval(&bytes, &mut event_log);
+        csv_to_interval(&bytes, &mut event_log, &TimestampParser::default()).unwrap();
 
         assert!(event_log.traces.len() > 2);
     }
@@ -242,7 +1539,7 @@ mod tests {
         let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         input_path.push("event_logs");
         input_path.push("Production.xes");
-        let log = lifecycle::parse_file(&input_path, true);
+        let log = lifecycle::parse_file(&input_path, true).unwrap();
 
         let mut xes_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         xes_path.push("test_output");
@@ -250,10 +1547,633 @@ mod tests {
 
         let mut xes_file = std::fs::File::create(xes_path.clone()).unwrap();
 
-        lifecycle_to_xes(&log, &mut xes_file);
+        lifecycle_to_xes(&log, &mut xes_file).unwrap();
 
         assert!(xes_path.exists());
 
         std::fs::remove_file(&xes_path).unwrap();
     }
+
+    #[test]
+    fn test_csv_to_interval_missing_column() {
+        let data = "case,activity,resource,start_time,end_time\
+        \n1,A,R1,1,2";
+
+        let mut event_log = EventLog {
+            traces: Vec::new(),
+            columns: EventLogColumns::default_style(),
+        };
+
+        let result = csv_to_interval(data.as_bytes(), &mut event_log, &TimestampParser::default());
+
+        assert!(matches!(result, Err(Error::MissingColumn { column }) if column == "variant"));
+    }
+
+    #[test]
+    fn test_csv_to_interval_optional_columns() {
+        let data = "case,activity,start_time,end_time\
+        \n1,A,1,2";
+
+        let mut event_log = EventLog {
+            traces: Vec::new(),
+            columns: EventLogColumns {
+                variant: None,
+                resource: None,
+                ..EventLogColumns::default_style()
+            },
+        };
+
+        csv_to_interval(data.as_bytes(), &mut event_log, &TimestampParser::new(vec![TimestampFormat::EpochSeconds]))
+            .unwrap();
+        let mut xes = Vec::new();
+        lifecycle_to_xes(&conversion::interval_to_lifecycle(&event_log).unwrap(), &mut xes).unwrap();
+
+        let xes = String::from_utf8(xes).unwrap();
+        assert!(xes.contains(r#"<string key="concept:name" value="A"/>"#));
+        assert!(!xes.contains("org:resource"));
+        assert!(!xes.contains(r#"key="variant""#));
+    }
+
+    #[test]
+    fn test_lifecycle_to_xes_keeps_attributes() {
+        let xes = r#"<log>
+            <trace>
+                <string key="concept:name" value="1"/>
+                <float key="cost" value="10.5"/>
+                <event>
+                    <string key="concept:name" value="A"/>
+                    <string key="org:role" value="clerk"/>
+                    <list key="tags"><values><int key="tag" value="1"/></values></list>
+                </event>
+            </trace>
+        </log>"#;
+        let log = lifecycle::EventLog {
+            header: LogHeader::default(),
+            traces: lifecycle::TraceReader::new(xes.as_bytes(), false)
+                .collect::<Result<Vec<_>>>()
+                .unwrap(),
+        };
+
+        let mut output = Vec::new();
+        lifecycle_to_xes(&log, &mut output).unwrap();
+        let traces = lifecycle::TraceReader::new(output.as_slice(), false)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(traces[0].attributes, log.traces[0].attributes);
+        assert_eq!(traces[0].events[0].attributes, log.traces[0].events[0].attributes);
+    }
+
+    #[test]
+    fn test_xes_writer_typed_attributes() {
+        let xes = r#"<log>
+            <trace>
+                <string key="concept:name" value="1"/>
+                <boolean key="rework" value="true"/>
+                <id key="order" value="0f8fad5b-d9cb-469f-a165-70867728950e"/>
+                <container key="customer"><string key="name" value="Ann"/></container>
+                <event>
+                    <string key="concept:name" value="A"/>
+                    <string key="org:resource" value=""/>
+                    <date key="time:timestamp" value="2023-01-01T10:00:00.000+00:00"/>
+                    <int key="items" value="3"/>
+                    <string key="note" value=""/>
+                </event>
+            </trace>
+        </log>"#;
+        let traces = lifecycle::TraceReader::new(xes.as_bytes(), false)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        let mut writer = XesWriter::indented(Vec::new(), &LogHeader::default(), 2).unwrap();
+        writer.write_trace(&traces[0]).unwrap();
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        assert!(output.contains("\n  <trace>\n    <string key=\"concept:name\" value=\"1\"/>\n"));
+        assert!(output.contains("<boolean key=\"rework\" value=\"true\"/>"));
+        assert!(output.contains("<id key=\"order\" value=\"0f8fad5b-d9cb-469f-a165-70867728950e\"/>"));
+        assert!(output.contains("<container key=\"customer\">"));
+        assert!(output.contains("<int key=\"items\" value=\"3\"/>"));
+        assert!(!output.contains("value=\"\""));
+        assert!(!output.contains("variant"));
+        assert!(!output.contains("lifecycle:transition"));
+
+        let read_traces = lifecycle::TraceReader::new(output.as_bytes(), false)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(read_traces[0].attributes, traces[0].attributes);
+        assert_eq!(read_traces[0].events[0].attributes.len(), 1);
+    }
+
+    #[test]
+    fn test_lifecycle_to_xes_keeps_header() {
+        let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        input_path.push("event_logs");
+        input_path.push("Production.xes");
+        let log = lifecycle::parse_file(&input_path, false).unwrap();
+
+        let mut output = Vec::new();
+        lifecycle_to_xes(&log, &mut output).unwrap();
+        let mut reader = lifecycle::TraceReader::new(output.as_slice(), false);
+
+        assert_eq!(reader.header().unwrap(), &log.header);
+    }
+
+    #[test]
+    fn test_lifecycle_to_xes_default_extensions() {
+        let log = lifecycle::EventLog::default();
+
+        let mut output = Vec::new();
+        lifecycle_to_xes(&log, &mut output).unwrap();
+        let mut reader = lifecycle::TraceReader::new(output.as_slice(), false);
+
+        assert_eq!(reader.header().unwrap().extensions, Extension::standard());
+    }
+
+    #[test]
+    fn test_csv_to_interval_extra_columns() {
+        let data = "case,variant,activity,resource,start_time,end_time,department,cost,code\
+        \n1,1,A,R1,1,2,Sales,10,007\
+        \n1,1,B,R2,3,4,Sales,2.5,008\
+        \n2,1,A,R1,5,6,Support,,009";
+
+        let mut event_log = EventLog {
+            traces: Vec::new(),
+            columns: EventLogColumns::default_style(),
+        };
+
+        csv_to_interval(data.as_bytes(), &mut event_log, &TimestampParser::new(vec![TimestampFormat::EpochSeconds])).unwrap();
+
+        let trace = &event_log.traces[0];
+        assert_eq!(
+            trace.attributes.get("department").unwrap().value,
+            AttributeValue::String("Sales".to_string())
+        );
+        assert_eq!(trace.events[0].attributes.get("cost").unwrap().value, AttributeValue::Float(10.0));
+        assert_eq!(
+            trace.events[0].attributes.get("code").unwrap().value,
+            AttributeValue::String("007".to_string())
+        );
+        assert!(event_log.traces[1].events[0].attributes.get("cost").is_none());
+    }
+
+    #[test]
+    fn test_column_type_of() {
+        for value in ["1.0", "10.50", "-0.5", "2.5e3", "0.25"] {
+            assert_eq!(ColumnType::of(value), ColumnType::Float, "{value}");
+        }
+        for value in ["NaN", "inf", "-infinity", "007", "00.5", "TRUE", "False", "1,5"] {
+            assert_eq!(ColumnType::of(value), ColumnType::String, "{value}");
+        }
+        assert_eq!(ColumnType::of("-12"), ColumnType::Int);
+        assert_eq!(ColumnType::of("true"), ColumnType::Boolean);
+    }
+
+    #[test]
+    fn test_csv_trace_reader() {
+        let data = "activity,case,start_time,end_time,resource,variant,department\
+        \nA,1,1,2,R1,1,Sales\
+        \nB,1,3,4,R2,1,Sales\
+        \nA,2,5,later,R1,1,Support";
+        let schema = CsvSchema::infer(data.as_bytes(), &EventLogColumns::default_style(), &CsvDialect::default()).unwrap();
+        let timestamps = TimestampParser::new(vec![TimestampFormat::EpochSeconds]);
+
+        let mut reader = CsvTraceReader::new(data.as_bytes(), &schema, timestamps, &Default::default()).unwrap();
+
+        let trace = reader.next().unwrap().unwrap();
+        assert_eq!(trace.case, "1");
+        assert_eq!(trace.events.len(), 2);
+        assert_eq!(trace.events[1].resource, "R2");
+        assert!(trace.attributes.get("department").is_some());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(Error::InvalidTimestamp { location, .. })) if location == "line 4, column 'end_time'"
+        ));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_csv_trace_reader_unsorted() {
+        let data = "case,variant,activity,resource,start_time,end_time,department\
+        \n2,1,A,R1,1,2,Support\
+        \n1,1,B,R2,4,5,Sales\
+        \n2,1,B,R1,3,4,Support\
+        \n3,1,A,R1,5,6,Sales\
+        \n1,1,A,R2,2,3,Support\
+        \n3,1,B,R1,later,7,Sales";
+        let schema = CsvSchema::infer(data.as_bytes(), &EventLogColumns::default_style(), &CsvDialect::default()).unwrap();
+        let timestamps = TimestampParser::new(vec![TimestampFormat::EpochSeconds]);
+
+        let mut reader =
+            CsvTraceReader::with_sort_run_rows(data.as_bytes(), &schema, timestamps, &Default::default(), 2).unwrap();
+
+        let traces = [reader.next().unwrap().unwrap(), reader.next().unwrap().unwrap()];
+        let cases = traces
+            .iter()
+            .map(|trace| {
+                let activities = trace.events.iter().map(|e| e.activity.as_str()).collect::<String>();
+                (trace.case.as_str(), activities)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(cases, [("2", "AB".to_string()), ("1", "AB".to_string())]);
+        assert!(traces[0].attributes.is_empty());
+        assert!(traces[0].events[0].attributes.get("department").is_some());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(Error::InvalidTimestamp { location, .. })) if location == "line 7, column 'start_time'"
+        ));
+    }
+
+    #[test]
+    fn test_csv_trace_reader_single_pass() {
+        let data = "case,variant,activity,resource,start_time,end_time,department,cost\
+        \n2,1,A,R1,1,2,Support,5\
+        \n2,1,B,R1,3,4,Support,7\
+        \n1,1,B,R2,4,5,Sales,1.5\
+        \n2,1,C,R1,5,6,Support,2\
+        \n1,1,A,R2,2,3,Sales,\
+        \n3,1,A,R1,later,7,Sales,3";
+        let timestamps = TimestampParser::new(vec![TimestampFormat::EpochSeconds]);
+
+        let columns = |headers: &csv::StringRecord| {
+            assert_eq!(&headers[0], "case");
+            EventLogColumns::default_style()
+        };
+        let mut reader = CsvTraceReader::single_pass(
+            data.as_bytes(),
+            &CsvDialect::default(),
+            columns,
+            timestamps,
+            &Default::default(),
+        )
+        .unwrap();
+
+        let traces = [reader.next().unwrap().unwrap(), reader.next().unwrap().unwrap()];
+        let activities = traces[0].events.iter().map(|e| e.activity.as_str()).collect::<String>();
+        assert_eq!((traces[0].case.as_str(), activities.as_str()), ("2", "ABC"));
+        assert_eq!(traces[1].case, "1");
+        assert!(traces[1].attributes.get("department").is_some());
+        assert_eq!(traces[0].events[0].attributes.get("cost").unwrap().value, AttributeValue::Float(5.0));
+        assert!(matches!(
+            reader.next(),
+            Some(Err(Error::InvalidTimestamp { location, .. })) if location == "line 7, column 'start_time'"
+        ));
+    }
+
+    #[test]
+    fn test_csv_single_timestamp_shapes() {
+        let data = "case,activity,timestamp,lifecycle\
+        \n1,A,1,start\
+        \n1,B,3,complete\
+        \n1,A,2,complete";
+        let columns = EventLogColumns {
+            variant: None,
+            resource: None,
+            shape: CsvShape::Lifecycle,
+            ..EventLogColumns::default_style()
+        };
+        let schema = CsvSchema::infer(data.as_bytes(), &columns, &CsvDialect::default()).unwrap();
+        let timestamps = TimestampParser::new(vec![TimestampFormat::EpochSeconds]);
+
+        let trace = CsvTraceReader::new(data.as_bytes(), &schema, timestamps.clone(), &Default::default())
+            .unwrap()
+            .lifecycle_traces()
+            .next()
+            .unwrap()
+            .unwrap();
+        let events = trace
+            .events
+            .iter()
+            .map(|e| (e.activity.as_str(), e.lifecycle.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(events, [("A", "start"), ("A", "complete"), ("B", "complete")]);
+        assert!(trace.events[0].attributes.get("lifecycle").is_none());
+
+        let columns = EventLogColumns {
+            shape: CsvShape::Atomic,
+            ..columns
+        };
+        let schema = CsvSchema::infer(data.as_bytes(), &columns, &CsvDialect::default()).unwrap();
+        let trace = CsvTraceReader::new(data.as_bytes(), &schema, timestamps, &Default::default())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(trace.events.len(), 3);
+        assert_eq!(trace.events[0].start_time, trace.events[0].end_time);
+        assert!(trace.events[0].attributes.get("lifecycle").is_some());
+    }
+
+    #[test]
+    fn test_csv_trace_reader_conversion() {
+        let data = "case,activity,timestamp,lifecycle\
+        \n1,A,1,start\
+        \n1,B,3,complete\
+        \n1,A,2,complete";
+        let columns = EventLogColumns {
+            variant: None,
+            resource: None,
+            shape: CsvShape::Lifecycle,
+            ..EventLogColumns::default_style()
+        };
+        let schema = CsvSchema::infer(data.as_bytes(), &columns, &CsvDialect::default()).unwrap();
+        let timestamps = TimestampParser::new(vec![TimestampFormat::EpochSeconds]);
+
+        let result = CsvTraceReader::new(data.as_bytes(), &schema, timestamps.clone(), &Default::default())
+            .unwrap()
+            .next()
+            .unwrap();
+        assert!(matches!(result, Err(Error::UnpairedEvents { .. })));
+
+        let options = ConversionOptions {
+            unpaired: conversion::UnpairedPolicy::Instant,
+            ..Default::default()
+        };
+        let trace = CsvTraceReader::new(data.as_bytes(), &schema, timestamps, &options)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let events = trace.events.iter().map(|e| e.activity.as_str()).collect::<Vec<_>>();
+        assert_eq!(events, ["A", "B"]);
+        assert_eq!(trace.events[1].start_time, trace.events[1].end_time);
+    }
+
+    #[test]
+    fn test_lifecycle_csv_writer() {
+        let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        input_path.push("event_logs");
+        input_path.push("Production.xes");
+
+        let log = lifecycle::parse_file(&input_path, true).unwrap();
+        let trace = &log.traces[0];
+
+        let mut lifecycle_csv = Vec::new();
+        LifecycleCsvWriter::new(&mut lifecycle_csv, Vec::new(), CsvShape::Lifecycle)
+            .write_trace(trace)
+            .unwrap();
+        let mut atomic_csv = Vec::new();
+        LifecycleCsvWriter::new(&mut atomic_csv, Vec::new(), CsvShape::Atomic)
+            .write_trace(trace)
+            .unwrap();
+
+        let lifecycle_csv = String::from_utf8(lifecycle_csv).unwrap();
+        let atomic_csv = String::from_utf8(atomic_csv).unwrap();
+        assert_eq!(lifecycle_csv.lines().count(), trace.events.len() + 1);
+        assert_eq!(atomic_csv.lines().count(), trace.events.len() / 2 + 1);
+        assert!(lifecycle_csv.starts_with("case,variant,activity,resource,timestamp,lifecycle\n"));
+        assert!(atomic_csv.starts_with("case,variant,activity,resource,timestamp\n"));
+        assert!(lifecycle_csv
+            .lines()
+            .nth(1)
+            .unwrap()
+            .ends_with("Turning & Milling,Machine 4,2022-01-03T08:10:00.000+00:00,start"));
+    }
+
+    #[test]
+    fn test_column_presets() {
+        let headers = |header: &str| csv::StringRecord::from(header.split(',').collect::<Vec<_>>());
+
+        let pm4py = headers("case:concept:name,concept:name,time:timestamp,cost");
+        let disco = headers("Case ID,Variant,Activity,Resource,Start Timestamp,Complete Timestamp");
+        let apromore = headers("Case ID,Activity,Start Time,End Time");
+        assert_eq!(ColumnPreset::detect(&pm4py), Some(ColumnPreset::Pm4py));
+        assert_eq!(ColumnPreset::detect(&disco), Some(ColumnPreset::Disco));
+        assert_eq!(ColumnPreset::detect(&apromore), Some(ColumnPreset::Apromore));
+        assert_eq!(ColumnPreset::detect(&headers("id,name,time")), None);
+
+        let mut columns = ColumnPreset::Pm4py.columns();
+        columns.fit_to_headers(&pm4py);
+        assert_eq!(columns.shape, CsvShape::Atomic);
+        assert_eq!(columns.resource, None);
+
+        let data = "case:concept:name,concept:name,time:timestamp,cost\n1,A,2022-01-01T10:00:00Z,5\n";
+        let schema = CsvSchema::infer(data.as_bytes(), &columns, &CsvDialect::default()).unwrap();
+        let traces = CsvTraceReader::new(data.as_bytes(), &schema, TimestampParser::default(), &Default::default())
+            .unwrap()
+            .lifecycle_traces()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        let mut csv = Vec::new();
+        LifecycleCsvWriter::new(&mut csv, vec!["cost".to_string()], CsvShape::Lifecycle)
+            .with_columns(ColumnPreset::Pm4py.columns())
+            .write_trace(&traces[0])
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "case:concept:name,concept:name,org:resource,time:timestamp,lifecycle:transition,cost\n\
+             1,A,,2022-01-01T10:00:00.000+00:00,complete,5\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_dialect() {
+        // "Café" and "Müller" in Windows-1252, without a header
+        let data = b"1;1;Caf\xe9;M\xfcller;1;2\n1;1;'Bill; Pay';'O''Neil';3;4\n";
+        let columns = EventLogColumns {
+            case: "1".to_string(),
+            variant: Some("2".to_string()),
+            activity: "3".to_string(),
+            resource: Some("4".to_string()),
+            start_time: "5".to_string(),
+            end_time: "6".to_string(),
+            ..EventLogColumns::default_style()
+        };
+        let dialect = CsvDialect {
+            delimiter: b';',
+            quote: b'\'',
+            has_header: false,
+            encoding: Encoding::for_label(b"windows-1252"),
+            ..Default::default()
+        };
+        let schema = CsvSchema::infer(&data[..], &columns, &dialect).unwrap();
+        let timestamps = TimestampParser::new(vec![TimestampFormat::EpochSeconds]);
+
+        let trace = CsvTraceReader::new(&data[..], &schema, timestamps, &Default::default())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(trace.events[0].activity, "Café");
+        assert_eq!(trace.events[0].resource, "Müller");
+        assert_eq!(trace.events[1].activity, "Bill; Pay");
+        assert_eq!(trace.events[1].resource, "O'Neil");
+
+        let mut output = Vec::new();
+        let mut writer = IntervalCsvWriter::with_dialect(&mut output, Vec::new(), &dialect);
+        writer.write_trace(&trace).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output.lines().next().unwrap(),
+            "1;1;Café;Müller;1970-01-01T00:00:01.000+00:00;1970-01-01T00:00:02.000+00:00"
+        );
+        assert!(output.lines().nth(1).unwrap().starts_with("1;1;'Bill; Pay';'O''Neil';"));
+    }
+
+    #[test]
+    fn test_xes_compressed_files() {
+        let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        input_path.push("event_logs");
+        input_path.push("Production.xes");
+        let log = lifecycle::parse_file(&input_path, false).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        for file_name in ["Production.xes.gz", "Production.xes.zst"] {
+            let path = dir.path().join(file_name);
+            lifecycle_to_xes_file(&log, &path).unwrap();
+            let start = std::fs::read(&path).unwrap();
+            assert_ne!(Compression::detect(&start), Compression::None);
+
+            let read_log = lifecycle::parse_file(&path, false).unwrap();
+            assert_eq!(read_log.traces.len(), log.traces.len());
+            assert_eq!(read_log.traces[0].events.len(), log.traces[0].events.len());
+        }
+    }
+
+    #[test]
+    fn test_csv_utf8_bom() {
+        let data = "\u{feff}case,variant,activity,resource,start_time,end_time\n1,1,A,R1,1,2";
+        let mut event_log = EventLog {
+            traces: Vec::new(),
+            columns: EventLogColumns::default_style(),
+        };
+
+        csv_to_interval(data.as_bytes(), &mut event_log, &TimestampParser::new(vec![TimestampFormat::EpochSeconds]))
+            .unwrap();
+
+        assert_eq!(event_log.traces[0].case, "1");
+    }
+
+    #[test]
+    fn test_extra_columns_round_trip() {
+        let data = "case,variant,activity,resource,start_time,end_time,department,cost\
+        \n1,1,A,R1,2022-01-03T08:00:00.000+00:00,2022-01-03T09:00:00.000+00:00,Sales,10\
+        \n1,1,B,R2,2022-01-03T09:30:00.000+00:00,2022-01-03T10:00:00.000+00:00,Sales,20\
+        \n2,1,A,R1,2022-01-03T08:30:00.000+02:00,2022-01-03T09:15:00.000+02:00,Support,30\n";
+
+        let mut event_log = EventLog {
+            traces: Vec::new(),
+            columns: EventLogColumns::default_style(),
+        };
+        csv_to_interval(data.as_bytes(), &mut event_log, &TimestampParser::default()).unwrap();
+
+        let mut xes = Vec::new();
+        lifecycle_to_xes(&conversion::interval_to_lifecycle(&event_log).unwrap(), &mut xes).unwrap();
+        let log = lifecycle::EventLog {
+            header: LogHeader::default(),
+            traces: lifecycle::TraceReader::new(xes.as_slice(), false)
+                .collect::<Result<Vec<_>>>()
+                .unwrap(),
+        };
+        assert_eq!(
+            log.traces[0].events[0].attributes.get("cost").unwrap().value,
+            AttributeValue::Int(10)
+        );
+
+        let mut csv = Vec::new();
+        interval_to_csv(&conversion::lifecycle_to_interval(&log, &Default::default()).unwrap(), &mut csv).unwrap();
+
+        assert_eq!(String::from_utf8(csv).unwrap(), data);
+    }
+
+    #[test]
+    fn test_detail_columns_round_trip() {
+        let data = "case,variant,activity,resource,start_time,end_time,\
+        completion,scheduled_time,suspensions,processing_time\
+        \n1,1,A,R1,2022-01-03T08:00:00.000+00:00,2022-01-03T09:00:00.000+00:00,complete,,,3600\
+        \n1,1,B,R2,2022-01-03T09:30:00.000+00:00,2022-01-03T10:00:00.000+00:00,ate_abort,2022-01-03T09:10:00.000+00:00,\
+        2022-01-03T09:40:00.000+00:00/2022-01-03T09:45:00.000+00:00 \
+        2022-01-03T09:50:00.000+00:00/2022-01-03T09:55:00.000+00:00,1200\n";
+
+        let mut event_log = EventLog {
+            traces: Vec::new(),
+            columns: EventLogColumns::default_style(),
+        };
+        csv_to_interval(data.as_bytes(), &mut event_log, &TimestampParser::default()).unwrap();
+
+        let event = &event_log.traces[0].events[1];
+        assert_eq!(event.completion, Completion::Aborted);
+        assert_eq!(event.scheduled_time.unwrap().to_rfc3339(), "2022-01-03T09:10:00+00:00");
+        assert_eq!(event.suspensions.len(), 2);
+        assert_eq!(event.suspensions[0].suspend_time.to_rfc3339(), "2022-01-03T09:40:00+00:00");
+        assert_eq!(event.processing_time(), chrono::Duration::minutes(20));
+        assert!(event.attributes.get(PROCESSING_TIME_COLUMN).is_none());
+
+        let mut csv = Vec::new();
+        interval_to_csv(&event_log, &mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), data);
+    }
+
+    #[test]
+    fn test_processing_time_column() {
+        let read = |processing_time: &str| {
+            let data = format!(
+                "case,variant,activity,resource,start_time,end_time,processing_time\
+                \n1,1,A,R1,2022-01-03T09:00:00.000+00:00,2022-01-03T10:00:00.000+00:00,{processing_time}\n"
+            );
+            let mut event_log = EventLog {
+                traces: Vec::new(),
+                columns: EventLogColumns::default_style(),
+            };
+            csv_to_interval(data.as_bytes(), &mut event_log, &TimestampParser::default()).map(|_| event_log)
+        };
+
+        // Without a suspensions column the processing time is only kept as an attribute
+        let event_log = read("1800.5").unwrap();
+        let event = &event_log.traces[0].events[0];
+        assert!(event.suspensions.is_empty());
+        assert_eq!(
+            event.attributes.get(PROCESSING_TIME_COLUMN).unwrap().value,
+            AttributeValue::Float(1800.5)
+        );
+
+        for value in ["-inf", "NaN", "-600", "3600.5", "1e300", "soon"] {
+            assert!(
+                matches!(read(value), Err(Error::InvalidAttribute { key, location, .. })
+                    if key == PROCESSING_TIME_COLUMN && location == "line 2"),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_csv_to_interval_invalid_completion() {
+        let data = "case,variant,activity,resource,start_time,end_time,completion\
+        \n1,1,A,R1,2022-01-03T08:00:00.000+00:00,2022-01-03T09:00:00.000+00:00,done\n";
+
+        let mut event_log = EventLog {
+            traces: Vec::new(),
+            columns: EventLogColumns::default_style(),
+        };
+        let result = csv_to_interval(data.as_bytes(), &mut event_log, &TimestampParser::default());
+
+        assert!(matches!(
+            result,
+            Err(Error::InvalidAttribute { key, value, .. }) if key == "completion" && value == "done"
+        ));
+    }
+
+    #[test]
+    fn test_csv_to_interval_invalid_timestamp() {
+        let data = "case,variant,activity,resource,start_time,end_time\
+        \n1,1,A,R1,2022-01-03 08:00:00,2022-01-03 09:00:00\
+        \n1,1,B,R1,2022-01-03 10:00:00,noon";
+
+        let mut event_log = EventLog {
+            traces: Vec::new(),
+            columns: EventLogColumns::default_style(),
+        };
+
+        let result = csv_to_interval(data.as_bytes(), &mut event_log, &TimestampParser::default());
+
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTimestamp { value: Some(value), location }) if value == "noon" && location == "line 3, column 'end_time'"
+        ));
+    }
 }
@@ -1,9 +1,17 @@
-#![feature(slice_group_by)]
-
 pub mod xes;
 pub mod io;
 pub mod conversion;
 pub mod cli;
+pub mod compression;
+pub mod mxml;
+pub mod ocel;
+pub mod parquet_io;
+pub mod config;
+pub mod format;
+pub mod error;
+pub mod timestamp;
+
+pub use error::{Error, Result};
 
 #[cfg(test)]
 mod tests {
@@ -20,15 +28,15 @@ mod tests {
     #[test]
     fn test_parse_xes() {
         let file_path = test_log_path();
-        let log = xes::lifecycle::parse_file(Path::new(&file_path), false);
+        let log = xes::lifecycle::parse_file(Path::new(&file_path), false).unwrap();
         println!("{log:?}");
     }
 
     #[test]
     fn test_lifecycle_to_event_log() {
         let file_path = test_log_path();
-        let mut log = xes::lifecycle::parse_file(Path::new(&file_path), false);
-        let event_log = crate::conversion::lifecycle_to_interval(&mut log);
+        let log = xes::lifecycle::parse_file(Path::new(&file_path), false).unwrap();
+        let event_log = crate::conversion::lifecycle_to_interval(&log, &Default::default()).unwrap();
         // println!("{event_log:?}");
         event_log.pretty_print();
     }
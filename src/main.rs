@@ -1,16 +1,23 @@
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
 
 use event_log_converter::cli;
 
 fn main() {
     let args = cli::Args::parse();
+    if args.input_log.is_none() && !matches!(args.action, cli::Action::Convert { .. }) {
+        cli::Args::command()
+            .error(ErrorKind::MissingRequiredArgument, "--input-log is required by xes-to-csv and csv-to-xes")
+            .exit();
+    }
+    if args.input_log.as_deref() == Some("-") && args.output_dir != "-" {
+        cli::Args::command()
+            .error(ErrorKind::ArgumentConflict, "standard input has no file name to name the output after, use -o -")
+            .exit();
+    }
 
-    match args.action {
-        cli::Action::XesToCsv => {
-            cli::convert_xes_to_csv(&args);
-        }
-        cli::Action::CsvToXes => {
-            cli::convert_csv_to_xes(&args);
-        }
+    if let Err(e) = cli::run(&args) {
+        eprintln!("error: {e}");
+        std::process::exit(cli::exit_code(&e));
     }
 }
@@ -0,0 +1,405 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event as XmlEvent};
+use quick_xml::Reader;
+
+use crate::compression;
+use crate::error::Result;
+use crate::timestamp;
+use crate::xes::attribute::{read_event, read_text, xml_error, Attribute, AttributeValue};
+use crate::xes::header::LogHeader;
+use crate::xes::lifecycle::{Event, EventLog, Trace, TraceFormat, XmlTraceReader};
+
+/// The MXML event types, which are the standard XES lifecycle transitions. Other transitions are written as
+/// `unknown` with an `unknowntype`.
+const EVENT_TYPES: [&str; 13] = [
+    "schedule",
+    "assign",
+    "withdraw",
+    "reassign",
+    "start",
+    "suspend",
+    "resume",
+    "pi_abort",
+    "ate_abort",
+    "complete",
+    "autoskip",
+    "manualskip",
+    "unknown",
+];
+
+/// Reads a whole MXML file, which may be gzip or Zstandard compressed.
+pub fn parse_file(file_name: &Path, filter_start_end_events: bool) -> Result<EventLog> {
+    let file = compression::decompress(File::open(file_name)?)?;
+    let mut reader = MxmlReader::new(BufReader::new(file), filter_start_end_events);
+    let header = reader.header()?.clone();
+
+    Ok(EventLog {
+        header,
+        traces: reader.collect::<Result<Vec<_>>>()?,
+    })
+}
+
+/// The ProM 5 MXML format.
+pub struct Mxml;
+
+/// Streaming reader of ProM 5 MXML logs that yields each `ProcessInstance` as a lifecycle trace.
+///
+/// `WorkflowModelElement` becomes the activity, `EventType` the lifecycle transition and `Originator` the resource.
+/// `Data` attributes are strings, and those of the log and its process become header attributes.
+pub type MxmlReader<R> = XmlTraceReader<R, Mxml>;
+
+impl TraceFormat for Mxml {
+    fn next_trace<R: BufRead>(reader: &mut MxmlReader<R>) -> Result<Option<Trace>> {
+        let mut current_trace: Option<Trace> = None;
+        let mut current_event: Option<Event> = None;
+
+        loop {
+            let (element, is_empty) = match read_event(&mut reader.reader, &mut reader.buf)? {
+                XmlEvent::Eof => {
+                    reader.header_read = true;
+                    return Ok(None);
+                }
+                XmlEvent::Start(e) => (e.into_owned(), false),
+                XmlEvent::Empty(e) => (e.into_owned(), true),
+                XmlEvent::End(e) => {
+                    match e.name().as_ref() {
+                        b"ProcessInstance" => return Ok(current_trace),
+                        b"AuditTrailEntry" => {
+                            if let (Some(trace), Some(event)) = (current_trace.as_mut(), current_event.take()) {
+                                reader.push_event(trace, event);
+                            }
+                        }
+                        _ => (),
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+
+            match element.name().as_ref() {
+                b"ProcessInstance" => {
+                    reader.header_read = true;
+                    let trace = Trace {
+                        case: attribute(&reader.reader, &element, b"id")?.unwrap_or_default(),
+                        ..Default::default()
+                    };
+                    if is_empty {
+                        return Ok(Some(trace));
+                    }
+                    current_trace = Some(trace);
+                }
+                b"Process" if current_trace.is_none() => {
+                    if let Some(id) = attribute(&reader.reader, &element, b"id")? {
+                        if reader.header.attributes.get("concept:name").is_none() {
+                            let name = Attribute::new("concept:name", AttributeValue::String(id));
+                            reader.header.attributes.insert(name);
+                        }
+                    }
+                }
+                b"AuditTrailEntry" if current_trace.is_some() && !is_empty => {
+                    current_event = Some(Event::default());
+                }
+                b"Attribute" if !is_empty => {
+                    let name = attribute(&reader.reader, &element, b"name")?.unwrap_or_default();
+                    let value = read_text(&mut reader.reader, &mut reader.buf, b"Attribute")?;
+                    let attribute = Attribute::new(&name, AttributeValue::String(value));
+                    if let Some(event) = current_event.as_mut() {
+                        event.set_attribute(attribute);
+                    } else if let Some(trace) = current_trace.as_mut() {
+                        trace.set_attribute(attribute);
+                    } else {
+                        reader.header.attributes.insert(attribute);
+                    }
+                }
+                name @ (b"WorkflowModelElement" | b"EventType" | b"Timestamp" | b"Originator")
+                    if current_event.is_some() && !is_empty =>
+                {
+                    let name = name.to_vec();
+                    let unknown_type = attribute(&reader.reader, &element, b"unknowntype")?;
+                    let position = reader.reader.buffer_position();
+                    let text = read_text(&mut reader.reader, &mut reader.buf, &name)?;
+                    let event = current_event.as_mut().unwrap();
+                    match name.as_slice() {
+                        b"WorkflowModelElement" => event.activity = text,
+                        b"Originator" => event.resource = text,
+                        b"EventType" => {
+                            event.lifecycle = match unknown_type {
+                                Some(unknown_type) if text == "unknown" && !unknown_type.is_empty() => unknown_type,
+                                _ => text,
+                            }
+                        }
+                        _ => event.timestamp = Some(reader.timestamps.parse(&text, || format!("byte {position}"))?),
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+fn attribute<R: BufRead>(reader: &Reader<R>, element: &BytesStart, key: &[u8]) -> Result<Option<String>> {
+    for a in element.attributes() {
+        let a = a.map_err(|e| xml_error(reader, e.into()))?;
+        if a.key.as_ref() == key {
+            let value = a.unescape_value().map_err(|e| xml_error(reader, e))?;
+            return Ok(Some(value.into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+/// Writes lifecycle traces to MXML one at a time, as the `ProcessInstance`s of a single `Process`.
+///
+/// The header's concept:name is the process id and its other attributes are log `Data`. Attributes are written as
+/// strings, leaving out lists, containers and empty values. Events without a lifecycle transition are `complete`.
+pub struct MxmlWriter<W: io::Write> {
+    writer: quick_xml::Writer<W>,
+}
+
+impl<W: io::Write> MxmlWriter<W> {
+    /// Writes the XML declaration and opens the `WorkflowLog` and `Process` elements.
+    pub fn new(writer: W, header: &LogHeader) -> Result<MxmlWriter<W>> {
+        MxmlWriter::start(quick_xml::Writer::new(writer), header)
+    }
+
+    /// Like [`MxmlWriter::new`], but puts every element on its own line, indented by `indent` spaces per level.
+    pub fn indented(writer: W, header: &LogHeader, indent: usize) -> Result<MxmlWriter<W>> {
+        MxmlWriter::start(quick_xml::Writer::new_with_indent(writer, b' ', indent), header)
+    }
+
+    fn start(mut writer: quick_xml::Writer<W>, header: &LogHeader) -> Result<MxmlWriter<W>> {
+        let xml_declaration = BytesDecl::new("1.0", Some("UTF-8"), None);
+        writer.write_event(XmlEvent::Decl(xml_declaration))?;
+
+        let mut log_element = BytesStart::new("WorkflowLog");
+        log_element.push_attribute(("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"));
+        log_element.push_attribute(("xsi:noNamespaceSchemaLocation", "WorkflowLog.xsd"));
+        writer.write_event(XmlEvent::Start(log_element))?;
+
+        let mut process_id = "process".to_string();
+        let mut data = Vec::new();
+        for attribute in &header.attributes {
+            match (attribute.key.as_str(), attribute.value.to_xes_value()) {
+                ("concept:name", Some(name)) if !name.is_empty() => process_id = name,
+                _ => data.push(attribute),
+            }
+        }
+        write_data(&mut writer, data)?;
+
+        let mut process_element = BytesStart::new("Process");
+        process_element.push_attribute(("id", process_id.as_str()));
+        writer.write_event(XmlEvent::Start(process_element))?;
+
+        Ok(MxmlWriter { writer })
+    }
+
+    pub fn write_trace(&mut self, trace: &Trace) -> Result<()> {
+        let variant = Attribute::new("variant", AttributeValue::String(trace.variant.clone()));
+        self.writer
+            .create_element("ProcessInstance")
+            .with_attribute(("id", trace.case.as_str()))
+            .write_inner_content(|w| {
+                write_data(w, std::iter::once(&variant).chain(&trace.attributes))?;
+                for event in &trace.events {
+                    w.create_element("AuditTrailEntry").write_inner_content(|w| {
+                        write_data(w, &event.attributes)?;
+                        w.create_element("WorkflowModelElement")
+                            .write_text_content(BytesText::new(&event.activity))?;
+
+                        let lifecycle = event.lifecycle.to_lowercase();
+                        let event_type = w.create_element("EventType");
+                        match lifecycle.as_str() {
+                            "" => event_type.write_text_content(BytesText::new("complete"))?,
+                            lifecycle if EVENT_TYPES.contains(&lifecycle) => {
+                                event_type.write_text_content(BytesText::new(lifecycle))?
+                            }
+                            _ => event_type
+                                .with_attribute(("unknowntype", event.lifecycle.as_str()))
+                                .write_text_content(BytesText::new("unknown"))?,
+                        };
+
+                        if let Some(timestamp) = &event.timestamp {
+                            w.create_element("Timestamp")
+                                .write_text_content(BytesText::new(&timestamp::format(timestamp)))?;
+                        }
+                        if !event.resource.is_empty() {
+                            w.create_element("Originator")
+                                .write_text_content(BytesText::new(&event.resource))?;
+                        }
+                        Ok(())
+                    })?;
+                }
+                Ok(())
+            })?;
+        Ok(())
+    }
+
+    /// Closes the `Process` and `WorkflowLog` elements and returns the underlying writer, which still has to be
+    /// flushed.
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.write_event(XmlEvent::End(BytesEnd::new("Process")))?;
+        self.writer.write_event(XmlEvent::End(BytesEnd::new("WorkflowLog")))?;
+        Ok(self.writer.into_inner())
+    }
+}
+
+/// Writes the attributes that have a non-empty value as a `Data` element, unless there are none.
+fn write_data<'a, W: io::Write>(
+    writer: &mut quick_xml::Writer<W>,
+    attributes: impl IntoIterator<Item = &'a Attribute>,
+) -> quick_xml::Result<()> {
+    let data = attributes
+        .into_iter()
+        .filter_map(|attribute| Some((attribute.key.as_str(), attribute.value.to_xes_value()?)))
+        .filter(|(_, value)| !value.is_empty())
+        .collect::<Vec<_>>();
+    if data.is_empty() {
+        return Ok(());
+    }
+
+    writer.create_element("Data").write_inner_content(|w| {
+        for (name, value) in &data {
+            w.create_element("Attribute")
+                .with_attribute(("name", *name))
+                .write_text_content(BytesText::new(value))?;
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Whether the bytes start an MXML document, i.e., XML with a `WorkflowLog` element.
+pub fn is_mxml(start: &[u8]) -> bool {
+    crate::format::is_xml(start)
+        && start
+            .windows(b"<WorkflowLog".len())
+            .any(|window| window == b"<WorkflowLog")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::error::Error;
+    use crate::xes::attribute::Attributes;
+    use crate::xes::lifecycle;
+
+    use super::*;
+
+    /// The attributes of a trace or event as MXML keeps them, for comparing logs before and after a round trip.
+    fn string_attributes(attributes: &Attributes) -> Vec<(String, String)> {
+        attributes
+            .iter()
+            .filter_map(|attribute| Some((attribute.key.clone(), attribute.value.to_xes_value()?)))
+            .collect()
+    }
+
+    #[test]
+    fn test_mxml_reader() {
+        let mxml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <WorkflowLog xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:noNamespaceSchemaLocation="WorkflowLog.xsd">
+            <Data><Attribute name="app.name">ProM</Attribute></Data>
+            <Source program="ProM"/>
+            <Process id="Production">
+                <ProcessInstance id="1" description="first case">
+                    <Data><Attribute name="priority">high</Attribute></Data>
+                    <AuditTrailEntry>
+                        <Data><Attribute name="cost">12.5</Attribute></Data>
+                        <WorkflowModelElement>Turning &amp; Milling</WorkflowModelElement>
+                        <EventType>start</EventType>
+                        <Timestamp>2012-01-29T23:24:00.000+01:00</Timestamp>
+                        <Originator>Machine 4</Originator>
+                    </AuditTrailEntry>
+                    <AuditTrailEntry>
+                        <WorkflowModelElement>Turning &amp; Milling</WorkflowModelElement>
+                        <EventType unknowntype="redo">unknown</EventType>
+                    </AuditTrailEntry>
+                </ProcessInstance>
+                <ProcessInstance id="2"/>
+            </Process>
+        </WorkflowLog>"#;
+
+        let mut reader = MxmlReader::new(mxml.as_bytes(), false);
+        let header = reader.header().unwrap().clone();
+        assert_eq!(
+            header.attributes.get("concept:name").unwrap().value,
+            AttributeValue::String("Production".to_string())
+        );
+        assert!(header.attributes.get("app.name").is_some());
+
+        let first = reader.next().unwrap().unwrap();
+        assert_eq!(first.case, "1");
+        assert_eq!(
+            string_attributes(&first.attributes),
+            [("priority".to_string(), "high".to_string())]
+        );
+        let event = &first.events[0];
+        assert_eq!(event.activity, "Turning & Milling");
+        assert_eq!(event.lifecycle, "start");
+        assert_eq!(event.resource, "Machine 4");
+        assert_eq!(
+            timestamp::format(&event.timestamp.unwrap()),
+            "2012-01-29T23:24:00.000+01:00"
+        );
+        assert_eq!(
+            string_attributes(&event.attributes),
+            [("cost".to_string(), "12.5".to_string())]
+        );
+        assert_eq!(first.events[1].lifecycle, "redo");
+        assert!(first.events[1].timestamp.is_none());
+
+        let second = reader.next().unwrap().unwrap();
+        assert_eq!(second.case, "2");
+        assert!(second.events.is_empty());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_mxml_reader_invalid_timestamp() {
+        let mxml = r#"<WorkflowLog><Process><ProcessInstance id="1"><AuditTrailEntry>
+            <Timestamp>yesterday</Timestamp>
+        </AuditTrailEntry></ProcessInstance></Process></WorkflowLog>"#;
+
+        let result = MxmlReader::new(mxml.as_bytes(), false).next().unwrap();
+
+        assert!(matches!(result, Err(Error::InvalidTimestamp { value: Some(value), .. }) if value == "yesterday"));
+    }
+
+    #[test]
+    fn test_mxml_round_trip() {
+        let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        input_path.push("event_logs");
+        input_path.push("Production.xes");
+        let log = lifecycle::parse_file(&input_path, false).unwrap();
+
+        let mut writer = MxmlWriter::indented(Vec::new(), &log.header, 2).unwrap();
+        for trace in &log.traces {
+            writer.write_trace(trace).unwrap();
+        }
+        let output = writer.finish().unwrap();
+        assert!(is_mxml(&output));
+
+        let traces = MxmlReader::new(output.as_slice(), false)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(traces.len(), log.traces.len());
+        for (trace, original) in traces.iter().zip(&log.traces) {
+            assert_eq!(trace.case, original.case);
+            assert_eq!(trace.variant, original.variant);
+            assert_eq!(
+                string_attributes(&trace.attributes),
+                string_attributes(&original.attributes)
+            );
+            assert_eq!(trace.events.len(), original.events.len());
+            for (event, original) in trace.events.iter().zip(&original.events) {
+                assert_eq!(event.activity, original.activity);
+                assert_eq!(event.resource, original.resource);
+                assert_eq!(event.timestamp, original.timestamp);
+                assert_eq!(event.lifecycle, original.lifecycle);
+            }
+        }
+    }
+}
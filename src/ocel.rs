@@ -0,0 +1,1207 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, Read, Write};
+
+use chrono::{TimeZone, Utc};
+use quick_xml::events::{BytesDecl, BytesText, Event as XmlEvent};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::timestamp::{self, Timestamp, TimestampParser};
+use crate::xes::attribute::{element_attributes, read_event, read_text, Attribute, AttributeValue, Attributes};
+use crate::xes::lifecycle::{self, Trace};
+
+/// An OCEL 2.0 object-centric event log, in which events relate to any number of objects of several types.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OcelLog {
+    pub object_types: Vec<OcelType>,
+    pub event_types: Vec<OcelType>,
+    pub objects: Vec<Object>,
+    pub events: Vec<Event>,
+}
+
+/// An object or event type with the types of its attributes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcelType {
+    pub name: String,
+    pub attributes: Vec<AttributeDeclaration>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeDeclaration {
+    pub name: String,
+    pub attribute_type: AttributeType,
+}
+
+/// The OCEL attribute types. XES IDs are strings, and lists and containers have no OCEL counterpart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeType {
+    String,
+    Time,
+    Integer,
+    Float,
+    Boolean,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object {
+    pub id: String,
+    pub object_type: String,
+    pub attributes: Vec<ObjectAttribute>,
+
+    /// Object-to-object relationships.
+    pub relationships: Vec<Relationship>,
+}
+
+/// The value an object attribute takes from `time` on. Values that never change have the Unix epoch as their time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectAttribute {
+    pub time: Timestamp,
+    pub attribute: Attribute,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub id: String,
+    pub event_type: String,
+    pub time: Timestamp,
+    pub attributes: Attributes,
+
+    /// The objects the event relates to.
+    pub relationships: Vec<Relationship>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relationship {
+    pub object_id: String,
+
+    /// The role of the object in the relationship, e.g., `customer`.
+    pub qualifier: String,
+}
+
+/// The object types of cases and resources, for flattening an OCEL log onto cases and lifting cases to objects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectMapping {
+    pub case_type: String,
+    pub resource_type: String,
+}
+
+impl Default for ObjectMapping {
+    fn default() -> Self {
+        ObjectMapping {
+            case_type: "case".to_string(),
+            resource_type: "resource".to_string(),
+        }
+    }
+}
+
+/// The two OCEL 2.0 file formats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OcelSyntax {
+    #[default]
+    Json,
+    Xml,
+}
+
+impl AttributeType {
+    pub fn name(self) -> &'static str {
+        match self {
+            AttributeType::String => "string",
+            AttributeType::Time => "time",
+            AttributeType::Integer => "integer",
+            AttributeType::Float => "float",
+            AttributeType::Boolean => "boolean",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<AttributeType> {
+        match name {
+            "string" => Some(AttributeType::String),
+            "time" => Some(AttributeType::Time),
+            "integer" => Some(AttributeType::Integer),
+            "float" => Some(AttributeType::Float),
+            "boolean" => Some(AttributeType::Boolean),
+            _ => None,
+        }
+    }
+
+    /// The type of an attribute value, if OCEL can hold it.
+    pub fn of(value: &AttributeValue) -> Option<AttributeType> {
+        match value {
+            AttributeValue::String(_) | AttributeValue::Id(_) => Some(AttributeType::String),
+            AttributeValue::Date(_) => Some(AttributeType::Time),
+            AttributeValue::Int(_) => Some(AttributeType::Integer),
+            AttributeValue::Float(_) => Some(AttributeType::Float),
+            AttributeValue::Boolean(_) => Some(AttributeType::Boolean),
+            AttributeValue::List(_) | AttributeValue::Container(_) => None,
+        }
+    }
+
+    /// The narrowest type that values of both types parse as.
+    fn widen(self, other: AttributeType) -> AttributeType {
+        match (self, other) {
+            (AttributeType::Integer, AttributeType::Float) | (AttributeType::Float, AttributeType::Integer) => {
+                AttributeType::Float
+            }
+            (attribute_type, other) if attribute_type == other => attribute_type,
+            _ => AttributeType::String,
+        }
+    }
+
+    fn parse(
+        self,
+        value: &str,
+        timestamps: &TimestampParser,
+        location: impl FnOnce() -> String,
+    ) -> Result<AttributeValue> {
+        let invalid = |location: String| Error::Ocel {
+            message: format!("invalid {} value '{value}' at {location}", self.name()),
+        };
+        Ok(match self {
+            AttributeType::String => AttributeValue::String(value.to_string()),
+            AttributeType::Time => AttributeValue::Date(timestamps.parse(value, location)?),
+            AttributeType::Integer => AttributeValue::Int(value.trim().parse().map_err(|_| invalid(location()))?),
+            AttributeType::Float => AttributeValue::Float(value.trim().parse().map_err(|_| invalid(location()))?),
+            AttributeType::Boolean => {
+                AttributeValue::Boolean(value.trim().to_lowercase().parse().map_err(|_| invalid(location()))?)
+            }
+        })
+    }
+}
+
+impl Object {
+    /// The latest `concept:name` of the object, which is the case or resource name of lifted logs.
+    pub fn name(&self) -> Option<String> {
+        self.attributes
+            .iter()
+            .filter(|attribute| attribute.attribute.key == "concept:name")
+            .max_by_key(|attribute| attribute.time)
+            .and_then(|attribute| attribute.attribute.value.to_xes_value())
+    }
+}
+
+impl OcelType {
+    pub fn new(name: &str) -> OcelType {
+        OcelType {
+            name: name.to_string(),
+            attributes: Vec::new(),
+        }
+    }
+
+    /// The declared type of an attribute. Undeclared attributes are strings.
+    pub fn attribute_type(&self, name: &str) -> AttributeType {
+        self.attributes
+            .iter()
+            .find(|declaration| declaration.name == name)
+            .map_or(AttributeType::String, |declaration| declaration.attribute_type)
+    }
+
+    /// Declares an attribute, widening its type if it is already declared with another one.
+    fn declare(&mut self, name: &str, attribute_type: AttributeType) {
+        match self.attributes.iter_mut().find(|declaration| declaration.name == name) {
+            Some(declaration) => declaration.attribute_type = declaration.attribute_type.widen(attribute_type),
+            None => self.attributes.push(AttributeDeclaration {
+                name: name.to_string(),
+                attribute_type,
+            }),
+        }
+    }
+}
+
+impl Relationship {
+    pub fn new(object_id: &str, qualifier: &str) -> Relationship {
+        Relationship {
+            object_id: object_id.to_string(),
+            qualifier: qualifier.to_string(),
+        }
+    }
+}
+
+/// The id of a lifted object, which is prefixed with its type so that objects of different types never share one.
+fn object_id(object_type: &str, name: &str) -> String {
+    format!("{object_type}:{name}")
+}
+
+/// The time of object attributes that do not change.
+fn epoch() -> Timestamp {
+    Utc.timestamp_opt(0, 0).unwrap().into()
+}
+
+impl OcelLog {
+    /// Reads an OCEL 2.0 JSON log. Timestamps without an offset are in the input timezone of `timestamps`.
+    pub fn from_json(reader: impl Read, timestamps: &TimestampParser) -> Result<OcelLog> {
+        let raw: RawLog = serde_json::from_reader(reader).map_err(json_error)?;
+        raw.into_log(timestamps)
+    }
+
+    /// Writes the log as OCEL 2.0 JSON, pretty-printed with `indent` spaces per level if given.
+    pub fn to_json(&self, writer: impl Write, indent: Option<usize>) -> Result<()> {
+        let raw = RawLog::from(self);
+        match indent {
+            Some(indent) => {
+                let indent = b" ".repeat(indent);
+                let formatter = serde_json::ser::PrettyFormatter::with_indent(&indent);
+                raw.serialize(&mut serde_json::Serializer::with_formatter(writer, formatter))
+            }
+            None => serde_json::to_writer(writer, &raw),
+        }
+        .map_err(json_error)
+    }
+
+    /// Reads an OCEL 2.0 XML log. Timestamps without an offset are in the input timezone of `timestamps`.
+    pub fn from_xml(reader: impl BufRead, timestamps: &TimestampParser) -> Result<OcelLog> {
+        RawLog::read_xml(reader)?.into_log(timestamps)
+    }
+
+    /// Writes the log as OCEL 2.0 XML, with `indent` spaces per level if given.
+    pub fn to_xml(&self, writer: impl Write, indent: Option<usize>) -> Result<()> {
+        let writer = match indent {
+            Some(indent) => quick_xml::Writer::new_with_indent(writer, b' ', indent),
+            None => quick_xml::Writer::new(writer),
+        };
+        RawLog::from(self).write_xml(writer)
+    }
+
+    /// A case-centric log with a trace for each object of the case type, holding the events related to it in time
+    /// order.
+    ///
+    /// Event types become activities and a related object of the resource type the resource, named by its
+    /// `concept:name` attribute or else its id. Events are `complete`
+    /// transitions unless they have a `lifecycle:transition` attribute, as OCEL events have no duration. The
+    /// attributes of the case object become trace attributes, taking the latest value of those that change over time.
+    pub fn flatten(&self, mapping: &ObjectMapping) -> Result<lifecycle::EventLog> {
+        let case_type = mapping.case_type.as_str();
+        let has_case_type = self
+            .object_types
+            .iter()
+            .any(|object_type| object_type.name == case_type)
+            || self.objects.iter().any(|object| object.object_type == case_type);
+        if !has_case_type {
+            return Err(Error::MissingObjectType {
+                object_type: mapping.case_type.clone(),
+            });
+        }
+
+        let objects: HashMap<&str, &Object> = self.objects.iter().map(|object| (object.id.as_str(), object)).collect();
+        let object_types: HashMap<&str, &str> = objects
+            .iter()
+            .map(|(&id, object)| (id, object.object_type.as_str()))
+            .collect();
+        let mut case_events: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, event) in self.events.iter().enumerate() {
+            for relationship in &event.relationships {
+                if object_types.get(relationship.object_id.as_str()) == Some(&case_type) {
+                    let events = case_events.entry(relationship.object_id.as_str()).or_default();
+                    if events.last() != Some(&index) {
+                        events.push(index);
+                    }
+                }
+            }
+        }
+
+        let mut traces = Vec::new();
+        for object in self.objects.iter().filter(|object| object.object_type == case_type) {
+            let mut trace = Trace {
+                case: object.id.clone(),
+                ..Default::default()
+            };
+            let mut attributes = object.attributes.iter().collect::<Vec<_>>();
+            attributes.sort_by_key(|attribute| attribute.time);
+            for attribute in attributes {
+                trace.set_attribute(attribute.attribute.clone());
+            }
+
+            let mut events = case_events.remove(object.id.as_str()).unwrap_or_default();
+            events.sort_by_key(|&index| self.events[index].time);
+            for index in events {
+                let event = &self.events[index];
+                let resource = event
+                    .relationships
+                    .iter()
+                    .find(|relationship| {
+                        object_types.get(relationship.object_id.as_str()) == Some(&mapping.resource_type.as_str())
+                    })
+                    .map(|relationship| {
+                        let id = relationship.object_id.as_str();
+                        objects[id].name().unwrap_or_else(|| id.to_string())
+                    })
+                    .unwrap_or_default();
+                let mut flat_event = lifecycle::Event {
+                    activity: event.event_type.clone(),
+                    resource,
+                    timestamp: Some(event.time),
+                    lifecycle: "complete".to_string(),
+                    ..Default::default()
+                };
+                for attribute in &event.attributes {
+                    flat_event.set_attribute(attribute.clone());
+                }
+                trace.events.push(flat_event);
+            }
+            traces.push(trace);
+        }
+
+        Ok(lifecycle::EventLog {
+            header: Default::default(),
+            traces,
+        })
+    }
+
+    /// An object-centric log with an object of the case type for each trace and one of the resource type for each
+    /// resource. Every event relates to its case and resource.
+    ///
+    /// Object ids are prefixed with the object type, e.g. `case:1` and `resource:1`, so that a case and a resource
+    /// with the same name are distinct objects. The names are kept as `concept:name` attributes of the objects.
+    ///
+    /// Trace attributes and the variant become attributes of the case object, and the lifecycle transition an event
+    /// attribute. Events need a timestamp, as OCEL events always have a time.
+    pub fn lift(traces: impl IntoIterator<Item = Result<Trace>>, mapping: &ObjectMapping) -> Result<OcelLog> {
+        let mut case_type = OcelType::new(&mapping.case_type);
+        let mut resource_type = OcelType::new(&mapping.resource_type);
+        let mut event_types: Vec<OcelType> = Vec::new();
+        let mut event_type_indices: HashMap<String, usize> = HashMap::new();
+        let mut cases = Vec::new();
+        let mut resources = Vec::new();
+        let mut resource_ids = HashSet::new();
+        let mut events = Vec::new();
+
+        for trace in traces {
+            let trace = trace?;
+            let case_id = object_id(&mapping.case_type, &trace.case);
+            let mut case = Object {
+                id: case_id.clone(),
+                object_type: mapping.case_type.clone(),
+                attributes: Vec::new(),
+                relationships: Vec::new(),
+            };
+            let name = Attribute::new("concept:name", AttributeValue::String(trace.case.clone()));
+            let variant = Attribute::new("variant", AttributeValue::String(trace.variant.clone()));
+            for attribute in [&name, &variant].into_iter().chain(&trace.attributes) {
+                if let Some(attribute_type) =
+                    AttributeType::of(&attribute.value).filter(|_| !attribute.value.is_empty())
+                {
+                    case_type.declare(&attribute.key, attribute_type);
+                    case.attributes.push(ObjectAttribute {
+                        time: epoch(),
+                        attribute: attribute.clone(),
+                    });
+                }
+            }
+
+            for (position, event) in trace.events.iter().enumerate() {
+                let time = event.timestamp.ok_or_else(|| Error::InvalidTimestamp {
+                    value: None,
+                    location: format!("case '{}', event {}", trace.case, position + 1),
+                })?;
+                let mut relationships = vec![Relationship::new(&case_id, &mapping.case_type)];
+                if !event.resource.is_empty() {
+                    let resource_id = object_id(&mapping.resource_type, &event.resource);
+                    if resource_ids.insert(event.resource.clone()) {
+                        let name = Attribute::new("concept:name", AttributeValue::String(event.resource.clone()));
+                        resource_type.declare(&name.key, AttributeType::String);
+                        resources.push(Object {
+                            id: resource_id.clone(),
+                            object_type: mapping.resource_type.clone(),
+                            attributes: vec![ObjectAttribute {
+                                time: epoch(),
+                                attribute: name,
+                            }],
+                            relationships: Vec::new(),
+                        });
+                    }
+                    relationships.push(Relationship::new(&resource_id, &mapping.resource_type));
+                }
+
+                let index = *event_type_indices.entry(event.activity.clone()).or_insert_with(|| {
+                    event_types.push(OcelType::new(&event.activity));
+                    event_types.len() - 1
+                });
+                let mut attributes = Attributes::new();
+                let lifecycle = Attribute::new("lifecycle:transition", AttributeValue::String(event.lifecycle.clone()));
+                for attribute in std::iter::once(&lifecycle).chain(&event.attributes) {
+                    if let Some(attribute_type) =
+                        AttributeType::of(&attribute.value).filter(|_| !attribute.value.is_empty())
+                    {
+                        event_types[index].declare(&attribute.key, attribute_type);
+                        attributes.insert(attribute.clone());
+                    }
+                }
+
+                events.push(Event {
+                    id: format!("e{}", events.len() + 1),
+                    event_type: event.activity.clone(),
+                    time,
+                    attributes,
+                    relationships,
+                });
+            }
+            cases.push(case);
+        }
+
+        cases.extend(resources);
+        Ok(OcelLog {
+            object_types: vec![case_type, resource_type],
+            event_types,
+            objects: cases,
+            events,
+        })
+    }
+}
+
+/// Whether the bytes start an OCEL 2.0 JSON log, i.e., a JSON object with object or event types.
+pub fn is_ocel_json(start: &[u8]) -> bool {
+    let start = start.strip_prefix(b"\xef\xbb\xbf").unwrap_or(start);
+    start.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'{')
+        && (contains(start, b"\"objectTypes\"") || contains(start, b"\"eventTypes\""))
+}
+
+/// Whether the bytes start an OCEL 2.0 XML log, i.e., XML with object or event types.
+pub fn is_ocel_xml(start: &[u8]) -> bool {
+    crate::format::is_xml(start) && (contains(start, b"<object-types") || contains(start, b"<event-types"))
+}
+
+fn contains(bytes: &[u8], pattern: &[u8]) -> bool {
+    bytes.windows(pattern.len()).any(|window| window == pattern)
+}
+
+fn json_error(e: serde_json::Error) -> Error {
+    if e.is_io() {
+        Error::Io(io::Error::from(e))
+    } else {
+        Error::Ocel { message: e.to_string() }
+    }
+}
+
+/// The OCEL document as it is serialized, with values still untyped. JSON and XML logs are both read into it, so
+/// attributes are typed the same way.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawLog {
+    #[serde(default)]
+    object_types: Vec<RawType>,
+    #[serde(default)]
+    event_types: Vec<RawType>,
+    #[serde(default)]
+    objects: Vec<RawObject>,
+    #[serde(default)]
+    events: Vec<RawEvent>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RawType {
+    name: String,
+    #[serde(default)]
+    attributes: Vec<RawDeclaration>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RawDeclaration {
+    name: String,
+    #[serde(rename = "type")]
+    attribute_type: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RawObject {
+    id: String,
+    #[serde(rename = "type")]
+    object_type: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attributes: Vec<RawAttribute>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    relationships: Vec<RawRelationship>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RawEvent {
+    id: String,
+    #[serde(rename = "type")]
+    event_type: String,
+    time: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attributes: Vec<RawAttribute>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    relationships: Vec<RawRelationship>,
+}
+
+/// An attribute value. Only object attributes have a time.
+#[derive(Debug, Serialize, Deserialize)]
+struct RawAttribute {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time: Option<String>,
+    value: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawRelationship {
+    object_id: String,
+    #[serde(default)]
+    qualifier: String,
+}
+
+/// An element of an OCEL XML log that is read up to its end tag.
+enum XmlItem {
+    ObjectType(RawType),
+    EventType(RawType),
+    Object(RawObject),
+    Event(RawEvent),
+}
+
+impl XmlItem {
+    fn is_item(name: &[u8]) -> bool {
+        matches!(name, b"object-type" | b"event-type" | b"object" | b"event")
+    }
+}
+
+impl RawType {
+    fn named(name: String) -> RawType {
+        RawType {
+            name,
+            attributes: Vec::new(),
+        }
+    }
+
+    fn into_type(self) -> Result<OcelType> {
+        let mut attributes = Vec::new();
+        for declaration in self.attributes {
+            let attribute_type = AttributeType::from_name(&declaration.attribute_type).ok_or_else(|| Error::Ocel {
+                message: format!(
+                    "unknown type '{}' of attribute '{}' of type '{}'",
+                    declaration.attribute_type, declaration.name, self.name
+                ),
+            })?;
+            attributes.push(AttributeDeclaration {
+                name: declaration.name,
+                attribute_type,
+            });
+        }
+        Ok(OcelType {
+            name: self.name,
+            attributes,
+        })
+    }
+}
+
+impl From<&OcelType> for RawType {
+    fn from(ocel_type: &OcelType) -> Self {
+        RawType {
+            name: ocel_type.name.clone(),
+            attributes: ocel_type
+                .attributes
+                .iter()
+                .map(|declaration| RawDeclaration {
+                    name: declaration.name.clone(),
+                    attribute_type: declaration.attribute_type.name().to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&Relationship> for RawRelationship {
+    fn from(relationship: &Relationship) -> Self {
+        RawRelationship {
+            object_id: relationship.object_id.clone(),
+            qualifier: relationship.qualifier.clone(),
+        }
+    }
+}
+
+impl From<RawRelationship> for Relationship {
+    fn from(relationship: RawRelationship) -> Self {
+        Relationship {
+            object_id: relationship.object_id,
+            qualifier: relationship.qualifier,
+        }
+    }
+}
+
+/// The text of a JSON value, which OCEL logs write as strings or as numbers and booleans.
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+fn json_value(value: &AttributeValue) -> Value {
+    match value {
+        AttributeValue::Int(value) => Value::from(*value),
+        AttributeValue::Float(value) => {
+            serde_json::Number::from_f64(*value).map_or_else(|| Value::String(value.to_string()), Value::Number)
+        }
+        AttributeValue::Boolean(value) => Value::Bool(*value),
+        value => Value::String(value.to_xes_value().unwrap_or_default()),
+    }
+}
+
+impl RawLog {
+    fn into_log(self, timestamps: &TimestampParser) -> Result<OcelLog> {
+        let object_types = self
+            .object_types
+            .into_iter()
+            .map(RawType::into_type)
+            .collect::<Result<Vec<_>>>()?;
+        let event_types = self
+            .event_types
+            .into_iter()
+            .map(RawType::into_type)
+            .collect::<Result<Vec<_>>>()?;
+        let find_type = |types: &[OcelType], name: &str| types.iter().position(|ocel_type| ocel_type.name == name);
+        let attribute_type = |types: &[OcelType], index: Option<usize>, name: &str| {
+            index.map_or(AttributeType::String, |index| types[index].attribute_type(name))
+        };
+
+        let mut objects = Vec::new();
+        for object in self.objects {
+            let type_index = find_type(&object_types, &object.object_type);
+            let mut attributes = Vec::new();
+            for attribute in object.attributes {
+                let location = || format!("object '{}', attribute '{}'", object.id, attribute.name);
+                let time = match &attribute.time {
+                    Some(time) => timestamps.parse(time, location)?,
+                    None => epoch(),
+                };
+                let value = attribute_type(&object_types, type_index, &attribute.name).parse(
+                    &value_text(&attribute.value),
+                    timestamps,
+                    location,
+                )?;
+                attributes.push(ObjectAttribute {
+                    time,
+                    attribute: Attribute::new(&attribute.name, value),
+                });
+            }
+            objects.push(Object {
+                id: object.id,
+                object_type: object.object_type,
+                attributes,
+                relationships: object.relationships.into_iter().map(Relationship::from).collect(),
+            });
+        }
+
+        let mut events = Vec::new();
+        for event in self.events {
+            let type_index = find_type(&event_types, &event.event_type);
+            let time = timestamps.parse(&event.time, || format!("event '{}'", event.id))?;
+            let mut attributes = Attributes::new();
+            for attribute in event.attributes {
+                let location = || format!("event '{}', attribute '{}'", event.id, attribute.name);
+                let value = attribute_type(&event_types, type_index, &attribute.name).parse(
+                    &value_text(&attribute.value),
+                    timestamps,
+                    location,
+                )?;
+                attributes.insert(Attribute::new(&attribute.name, value));
+            }
+            events.push(Event {
+                id: event.id,
+                event_type: event.event_type,
+                time,
+                attributes,
+                relationships: event.relationships.into_iter().map(Relationship::from).collect(),
+            });
+        }
+
+        Ok(OcelLog {
+            object_types,
+            event_types,
+            objects,
+            events,
+        })
+    }
+
+    fn read_xml(reader: impl BufRead) -> Result<RawLog> {
+        let mut reader = Reader::from_reader(reader);
+        reader.trim_text(true);
+        reader.expand_empty_elements(false);
+        let mut buf = Vec::new();
+        let mut log = RawLog::default();
+        let mut current: Option<XmlItem> = None;
+
+        loop {
+            let (element, is_empty) = match read_event(&mut reader, &mut buf)? {
+                XmlEvent::Eof => return Ok(log),
+                XmlEvent::Start(e) => (e.into_owned(), false),
+                XmlEvent::Empty(e) => (e.into_owned(), true),
+                XmlEvent::End(e) => {
+                    if XmlItem::is_item(e.name().as_ref()) {
+                        log.push(current.take());
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+
+            let mut xml_attributes = element_attributes(&reader, &element)?
+                .into_iter()
+                .collect::<HashMap<_, _>>();
+            let mut take = |key: &str| xml_attributes.remove(key).unwrap_or_default();
+            match element.name().as_ref() {
+                b"object-type" => current = Some(XmlItem::ObjectType(RawType::named(take("name")))),
+                b"event-type" => current = Some(XmlItem::EventType(RawType::named(take("name")))),
+                b"object" => {
+                    current = Some(XmlItem::Object(RawObject {
+                        id: take("id"),
+                        object_type: take("type"),
+                        ..Default::default()
+                    }))
+                }
+                b"event" => {
+                    current = Some(XmlItem::Event(RawEvent {
+                        id: take("id"),
+                        event_type: take("type"),
+                        time: take("time"),
+                        ..Default::default()
+                    }))
+                }
+                b"attribute" => match current.as_mut() {
+                    Some(XmlItem::ObjectType(raw_type) | XmlItem::EventType(raw_type)) => {
+                        raw_type.attributes.push(RawDeclaration {
+                            name: take("name"),
+                            attribute_type: take("type"),
+                        })
+                    }
+                    Some(
+                        XmlItem::Object(RawObject { attributes, .. }) | XmlItem::Event(RawEvent { attributes, .. }),
+                    ) => {
+                        let name = take("name");
+                        // Object attributes have a time, but event attributes do not
+                        let time = Some(take("time")).filter(|time| !time.is_empty());
+                        let value = if is_empty {
+                            String::new()
+                        } else {
+                            read_text(&mut reader, &mut buf, b"attribute")?
+                        };
+                        attributes.push(RawAttribute {
+                            name,
+                            time,
+                            value: Value::String(value),
+                        });
+                    }
+                    None => (),
+                },
+                b"relationship" => {
+                    if let Some(
+                        XmlItem::Object(RawObject { relationships, .. })
+                        | XmlItem::Event(RawEvent { relationships, .. }),
+                    ) = current.as_mut()
+                    {
+                        relationships.push(RawRelationship {
+                            object_id: take("object-id"),
+                            qualifier: take("qualifier"),
+                        });
+                    }
+                }
+                _ => (),
+            }
+            if is_empty && XmlItem::is_item(element.name().as_ref()) {
+                log.push(current.take());
+            }
+        }
+    }
+
+    fn push(&mut self, item: Option<XmlItem>) {
+        match item {
+            Some(XmlItem::ObjectType(object_type)) => self.object_types.push(object_type),
+            Some(XmlItem::EventType(event_type)) => self.event_types.push(event_type),
+            Some(XmlItem::Object(object)) => self.objects.push(object),
+            Some(XmlItem::Event(event)) => self.events.push(event),
+            None => (),
+        }
+    }
+
+    fn write_xml<W: Write>(&self, mut writer: quick_xml::Writer<W>) -> Result<()> {
+        writer.write_event(XmlEvent::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+        writer.create_element("log").write_inner_content(|w| {
+            for (section, element, types) in [
+                ("object-types", "object-type", &self.object_types),
+                ("event-types", "event-type", &self.event_types),
+            ] {
+                w.create_element(section).write_inner_content(|w| {
+                    for raw_type in types {
+                        w.create_element(element)
+                            .with_attribute(("name", raw_type.name.as_str()))
+                            .write_inner_content(|w| {
+                                w.create_element("attributes").write_inner_content(|w| {
+                                    for declaration in &raw_type.attributes {
+                                        w.create_element("attribute")
+                                            .with_attribute(("name", declaration.name.as_str()))
+                                            .with_attribute(("type", declaration.attribute_type.as_str()))
+                                            .write_empty()?;
+                                    }
+                                    Ok(())
+                                })?;
+                                Ok(())
+                            })?;
+                    }
+                    Ok(())
+                })?;
+            }
+
+            w.create_element("objects").write_inner_content(|w| {
+                for object in &self.objects {
+                    w.create_element("object")
+                        .with_attribute(("id", object.id.as_str()))
+                        .with_attribute(("type", object.object_type.as_str()))
+                        .write_inner_content(|w| write_xml_contents(w, &object.attributes, &object.relationships))?;
+                }
+                Ok(())
+            })?;
+
+            w.create_element("events").write_inner_content(|w| {
+                for event in &self.events {
+                    w.create_element("event")
+                        .with_attribute(("id", event.id.as_str()))
+                        .with_attribute(("type", event.event_type.as_str()))
+                        .with_attribute(("time", event.time.as_str()))
+                        .write_inner_content(|w| write_xml_contents(w, &event.attributes, &event.relationships))?;
+                }
+                Ok(())
+            })?;
+            Ok(())
+        })?;
+        writer.into_inner().flush()?;
+        Ok(())
+    }
+}
+
+/// Writes the attributes and relationships of an object or event.
+fn write_xml_contents<W: Write>(
+    writer: &mut quick_xml::Writer<W>,
+    attributes: &[RawAttribute],
+    relationships: &[RawRelationship],
+) -> quick_xml::Result<()> {
+    if !attributes.is_empty() {
+        writer.create_element("attributes").write_inner_content(|w| {
+            for attribute in attributes {
+                let mut element = w
+                    .create_element("attribute")
+                    .with_attribute(("name", attribute.name.as_str()));
+                if let Some(time) = &attribute.time {
+                    element = element.with_attribute(("time", time.as_str()));
+                }
+                element.write_text_content(BytesText::new(&value_text(&attribute.value)))?;
+            }
+            Ok(())
+        })?;
+    }
+    if !relationships.is_empty() {
+        writer.create_element("objects").write_inner_content(|w| {
+            for relationship in relationships {
+                w.create_element("relationship")
+                    .with_attribute(("object-id", relationship.object_id.as_str()))
+                    .with_attribute(("qualifier", relationship.qualifier.as_str()))
+                    .write_empty()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+impl From<&OcelLog> for RawLog {
+    fn from(log: &OcelLog) -> Self {
+        let raw_attribute = |attribute: &Attribute, time: Option<&Timestamp>| {
+            AttributeType::of(&attribute.value).map(|_| RawAttribute {
+                name: attribute.key.clone(),
+                time: time.map(timestamp::format),
+                value: json_value(&attribute.value),
+            })
+        };
+
+        RawLog {
+            object_types: log.object_types.iter().map(RawType::from).collect(),
+            event_types: log.event_types.iter().map(RawType::from).collect(),
+            objects: log
+                .objects
+                .iter()
+                .map(|object| RawObject {
+                    id: object.id.clone(),
+                    object_type: object.object_type.clone(),
+                    attributes: object
+                        .attributes
+                        .iter()
+                        .filter_map(|attribute| raw_attribute(&attribute.attribute, Some(&attribute.time)))
+                        .collect(),
+                    relationships: object.relationships.iter().map(RawRelationship::from).collect(),
+                })
+                .collect(),
+            events: log
+                .events
+                .iter()
+                .map(|event| RawEvent {
+                    id: event.id.clone(),
+                    event_type: event.event_type.clone(),
+                    time: timestamp::format(&event.time),
+                    attributes: event
+                        .attributes
+                        .iter()
+                        .filter_map(|attribute| raw_attribute(attribute, None))
+                        .collect(),
+                    relationships: event.relationships.iter().map(RawRelationship::from).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::conversion::{self, ConversionOptions, UnpairedPolicy};
+
+    use super::*;
+
+    const JSON_LOG: &str = r#"{
+        "objectTypes": [
+            {"name": "order", "attributes": [{"name": "price", "type": "float"}]},
+            {"name": "employee", "attributes": []}
+        ],
+        "eventTypes": [
+            {"name": "place order", "attributes": [{"name": "items", "type": "integer"}]},
+            {"name": "pay order", "attributes": []}
+        ],
+        "objects": [
+            {"id": "o1", "type": "order", "attributes": [
+                {"name": "price", "time": "2023-01-01T00:00:00Z", "value": 10},
+                {"name": "price", "time": "1970-01-01T00:00:00Z", "value": "8.5"}
+            ]},
+            {"id": "o2", "type": "order"},
+            {"id": "Ann", "type": "employee"}
+        ],
+        "events": [
+            {"id": "e2", "type": "pay order", "time": "2023-01-02T09:00:00Z",
+                "relationships": [{"objectId": "o1", "qualifier": "order"}]},
+            {"id": "e1", "type": "place order", "time": "2023-01-01T09:00:00Z",
+                "attributes": [{"name": "items", "value": "3"}],
+                "relationships": [
+                    {"objectId": "o1", "qualifier": "order"},
+                    {"objectId": "o2", "qualifier": "order"},
+                    {"objectId": "Ann", "qualifier": "clerk"}
+                ]}
+        ]
+    }"#;
+
+    const XML_LOG: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+    <log>
+        <object-types>
+            <object-type name="order">
+                <attributes><attribute name="price" type="float"/></attributes>
+            </object-type>
+            <object-type name="employee"><attributes/></object-type>
+        </object-types>
+        <event-types>
+            <event-type name="place order">
+                <attributes><attribute name="items" type="integer"/></attributes>
+            </event-type>
+            <event-type name="pay order"/>
+        </event-types>
+        <objects>
+            <object id="o1" type="order">
+                <attributes>
+                    <attribute name="price" time="2023-01-01T00:00:00Z">10</attribute>
+                    <attribute name="price" time="1970-01-01T00:00:00Z">8.5</attribute>
+                </attributes>
+            </object>
+            <object id="o2" type="order"/>
+            <object id="Ann" type="employee"/>
+        </objects>
+        <events>
+            <event id="e2" type="pay order" time="2023-01-02T09:00:00Z">
+                <objects><relationship object-id="o1" qualifier="order"/></objects>
+            </event>
+            <event id="e1" type="place order" time="2023-01-01T09:00:00Z">
+                <attributes><attribute name="items">3</attribute></attributes>
+                <objects>
+                    <relationship object-id="o1" qualifier="order"/>
+                    <relationship object-id="o2" qualifier="order"/>
+                    <relationship object-id="Ann" qualifier="clerk"/>
+                </objects>
+            </event>
+        </events>
+    </log>"#;
+
+    fn mapping() -> ObjectMapping {
+        ObjectMapping {
+            case_type: "order".to_string(),
+            resource_type: "employee".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_ocel_json_and_xml() {
+        let timestamps = TimestampParser::default();
+        let json = OcelLog::from_json(JSON_LOG.as_bytes(), &timestamps).unwrap();
+        let xml = OcelLog::from_xml(XML_LOG.as_bytes(), &timestamps).unwrap();
+
+        assert_eq!(json, xml);
+        assert_eq!(
+            json.objects[0].attributes[0].attribute.value,
+            AttributeValue::Float(10.0)
+        );
+        assert_eq!(
+            json.events[1].attributes.get("items").unwrap().value,
+            AttributeValue::Int(3)
+        );
+        assert_eq!(json.events[1].relationships[2], Relationship::new("Ann", "clerk"));
+    }
+
+    #[test]
+    fn test_ocel_flatten() {
+        let log = OcelLog::from_json(JSON_LOG.as_bytes(), &TimestampParser::default()).unwrap();
+
+        let traces = log.flatten(&mapping()).unwrap().traces;
+
+        assert_eq!(traces.len(), 2);
+        assert_eq!(traces[0].case, "o1");
+        assert_eq!(
+            traces[0].attributes.get("price").unwrap().value,
+            AttributeValue::Float(10.0)
+        );
+        let activities = traces[0].events.iter().map(|e| e.activity.as_str()).collect::<Vec<_>>();
+        assert_eq!(activities, ["place order", "pay order"]);
+        assert_eq!(traces[0].events[0].resource, "Ann");
+        assert_eq!(traces[0].events[1].resource, "");
+        assert_eq!(traces[0].events[0].lifecycle, "complete");
+        assert_eq!(traces[1].case, "o2");
+        assert_eq!(traces[1].events.len(), 1);
+
+        assert!(matches!(
+            log.flatten(&ObjectMapping::default()),
+            Err(Error::MissingObjectType { object_type }) if object_type == "case"
+        ));
+    }
+
+    #[test]
+    fn test_ocel_invalid_value() {
+        let json = JSON_LOG.replace(r#""value": "3""#, r#""value": "three""#);
+
+        let result = OcelLog::from_json(json.as_bytes(), &TimestampParser::default());
+
+        assert!(matches!(result, Err(Error::Ocel { message }) if message.contains("'three'")));
+    }
+
+    #[test]
+    fn test_ocel_lift_round_trip() {
+        let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        input_path.push("event_logs");
+        input_path.push("Production.xes");
+        let original = lifecycle::parse_file(&input_path, false).unwrap();
+
+        let log = OcelLog::lift(original.traces.iter().cloned().map(Ok), &ObjectMapping::default()).unwrap();
+        let resources = log
+            .objects
+            .iter()
+            .filter(|object| object.object_type == "resource")
+            .count();
+        assert!(resources > 0);
+
+        for syntax in [OcelSyntax::Json, OcelSyntax::Xml] {
+            let mut output = Vec::new();
+            let read_log = match syntax {
+                OcelSyntax::Json => {
+                    log.to_json(&mut output, None).unwrap();
+                    assert!(is_ocel_json(&output));
+                    OcelLog::from_json(output.as_slice(), &TimestampParser::default()).unwrap()
+                }
+                OcelSyntax::Xml => {
+                    log.to_xml(&mut output, Some(2)).unwrap();
+                    assert!(is_ocel_xml(&output));
+                    OcelLog::from_xml(output.as_slice(), &TimestampParser::default()).unwrap()
+                }
+            };
+            assert_eq!(read_log, log);
+
+            let traces = read_log.flatten(&ObjectMapping::default()).unwrap().traces;
+            assert_eq!(traces.len(), original.traces.len());
+            for (trace, original) in traces.iter().zip(&original.traces) {
+                assert_eq!(trace.case, original.case);
+                assert_eq!(trace.variant, original.variant);
+                assert_eq!(trace.events.len(), original.events.len());
+                for (event, original) in trace.events.iter().zip(&original.events) {
+                    assert_eq!(event.activity, original.activity);
+                    assert_eq!(event.resource, original.resource);
+                    assert_eq!(event.timestamp, original.timestamp);
+                    assert_eq!(event.lifecycle, original.lifecycle);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ocel_lift_shared_names() {
+        let trace = Trace {
+            case: "1".to_string(),
+            events: vec![lifecycle::Event {
+                activity: "A".to_string(),
+                resource: "1".to_string(),
+                timestamp: Some(chrono::DateTime::parse_from_rfc3339("2022-01-01T09:00:00Z").unwrap()),
+                lifecycle: "complete".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let log = OcelLog::lift([Ok(trace)], &ObjectMapping::default()).unwrap();
+        let ids = log.objects.iter().map(|object| object.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, ["case:1", "resource:1"]);
+        assert_eq!(log.objects[1].name().as_deref(), Some("1"));
+
+        let traces = log.flatten(&ObjectMapping::default()).unwrap().traces;
+        assert_eq!(traces.len(), 1);
+        assert_eq!(traces[0].case, "1");
+        assert_eq!(traces[0].events.len(), 1);
+        assert_eq!(traces[0].events[0].resource, "1");
+    }
+
+    #[test]
+    fn test_ocel_interval_round_trip() {
+        let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        input_path.push("event_logs");
+        input_path.push("Production.xes");
+        let options = ConversionOptions::default();
+        let intervals = lifecycle::parse_file(&input_path, false)
+            .unwrap()
+            .traces
+            .iter()
+            .map(|trace| conversion::lifecycle_trace_to_interval(trace, &options))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        let traces = intervals.iter().map(|trace| Ok(conversion::interval_trace_to_lifecycle(trace)));
+        let log = OcelLog::lift(traces, &ObjectMapping::default()).unwrap();
+        let mut output = Vec::new();
+        log.to_json(&mut output, None).unwrap();
+        let read_log = OcelLog::from_json(output.as_slice(), &TimestampParser::default()).unwrap();
+        let flattened = read_log.flatten(&ObjectMapping::default()).unwrap().traces;
+
+        assert_eq!(flattened.len(), intervals.len());
+        for (trace, original) in flattened.iter().zip(&intervals) {
+            let trace = conversion::lifecycle_trace_to_interval(trace, &options).unwrap();
+            assert_eq!(trace.events.len(), original.events.len());
+            assert!(!trace.events.is_empty());
+            for (event, original) in trace.events.iter().zip(&original.events) {
+                assert_eq!(event.activity, original.activity);
+                assert_eq!(event.start_time, original.start_time);
+                assert_eq!(event.end_time, original.end_time);
+            }
+        }
+
+        // Events of native OCEL logs are complete transitions without a start
+        let native = OcelLog::from_json(JSON_LOG.as_bytes(), &TimestampParser::default()).unwrap();
+        let options = ConversionOptions {
+            unpaired: UnpairedPolicy::Instant,
+            ..Default::default()
+        };
+        let trace = &native.flatten(&mapping()).unwrap().traces[0];
+        let trace = conversion::lifecycle_trace_to_interval(trace, &options).unwrap();
+        assert_eq!(trace.events.len(), 2);
+        assert_eq!(trace.events[0].start_time, trace.events[0].end_time);
+    }
+}
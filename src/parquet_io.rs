@@ -0,0 +1,1214 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Write;
+use std::sync::Arc;
+
+use arrow_array::types::Int32Type;
+use arrow_array::{
+    Array, ArrayRef, BooleanArray, DictionaryArray, Float64Array, Int64Array, RecordBatch, StringArray,
+    TimestampMillisecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{TimeZone, Utc};
+use chrono_tz::Tz;
+use parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+
+use crate::conversion;
+use crate::error::{Error, Result};
+use crate::io::{
+    CsvShape, DetailColumns, EventLogColumns, COMPLETION_COLUMN, PROCESSING_TIME_COLUMN, SCHEDULED_TIME_COLUMN,
+    SUSPENSIONS_COLUMN,
+};
+use crate::timestamp::Timestamp;
+use crate::xes::attribute::{Attribute, AttributeValue, Attributes};
+use crate::xes::interval::Completion;
+use crate::xes::{interval, lifecycle};
+
+/// Field metadata key that marks the columns of trace attributes, which hold the same value in every row of a case.
+const SCOPE: &str = "scope";
+
+/// Rows per row group of written files, unless another size is given.
+pub const DEFAULT_ROW_GROUP_SIZE: usize = 65536;
+
+/// The type of an extra attribute column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    String,
+    Int,
+    Float,
+    Boolean,
+    Date,
+}
+
+impl ValueType {
+    /// The column type of an attribute value. Lists and containers have no column type and are left out.
+    pub fn of(value: &AttributeValue) -> Option<ValueType> {
+        match value {
+            AttributeValue::String(_) | AttributeValue::Id(_) => Some(ValueType::String),
+            AttributeValue::Int(_) => Some(ValueType::Int),
+            AttributeValue::Float(_) => Some(ValueType::Float),
+            AttributeValue::Boolean(_) => Some(ValueType::Boolean),
+            AttributeValue::Date(_) => Some(ValueType::Date),
+            AttributeValue::List(_) | AttributeValue::Container(_) => None,
+        }
+    }
+
+    /// The narrowest type that values of both types can be written as.
+    fn widen(self, other: ValueType) -> ValueType {
+        match (self, other) {
+            (ValueType::Int, ValueType::Float) | (ValueType::Float, ValueType::Int) => ValueType::Float,
+            (value_type, other) if value_type == other => value_type,
+            _ => ValueType::String,
+        }
+    }
+
+    fn data_type(self) -> DataType {
+        match self {
+            ValueType::String => DataType::Utf8,
+            ValueType::Int => DataType::Int64,
+            ValueType::Float => DataType::Float64,
+            ValueType::Boolean => DataType::Boolean,
+            ValueType::Date => timestamp_type(),
+        }
+    }
+
+    /// The type that a column of another Parquet file is read as, if any.
+    fn of_data_type(data_type: &DataType) -> Option<ValueType> {
+        match data_type {
+            DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => Some(ValueType::String),
+            DataType::Dictionary(_, value_type) => ValueType::of_data_type(value_type),
+            data_type if data_type.is_integer() => Some(ValueType::Int),
+            data_type if data_type.is_floating() => Some(ValueType::Float),
+            DataType::Boolean => Some(ValueType::Boolean),
+            DataType::Timestamp(..) | DataType::Date32 | DataType::Date64 => Some(ValueType::Date),
+            _ => None,
+        }
+    }
+}
+
+fn timestamp_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+}
+
+fn dictionary_type() -> DataType {
+    DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8))
+}
+
+/// An extra column with the values of an attribute key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeColumn {
+    pub key: String,
+    pub value_type: ValueType,
+
+    /// Whether the key is only a trace attribute, so the column is marked as such and read back as one.
+    pub is_trace_attribute: bool,
+}
+
+/// Adds the attribute keys of an interval trace to the extra columns, widening the types of known keys.
+pub fn add_interval_attribute_columns(columns: &mut Vec<AttributeColumn>, trace: &interval::Trace) {
+    add_columns(
+        columns,
+        &trace.attributes,
+        trace.events.iter().map(|event| &event.attributes),
+    );
+}
+
+pub fn add_lifecycle_attribute_columns(columns: &mut Vec<AttributeColumn>, trace: &lifecycle::Trace) {
+    add_columns(
+        columns,
+        &trace.attributes,
+        trace.events.iter().map(|event| &event.attributes),
+    );
+}
+
+/// Whether an attribute has a column of its scope that its value can be written to without widening the type.
+pub fn has_column(columns: &[AttributeColumn], attribute: &Attribute, is_trace_attribute: bool) -> bool {
+    let Some(value_type) = ValueType::of(&attribute.value) else {
+        return true;
+    };
+    columns.iter().any(|column| {
+        column.key == attribute.key
+            && column.value_type.widen(value_type) == column.value_type
+            && (is_trace_attribute || !column.is_trace_attribute)
+    })
+}
+
+fn add_columns<'a>(
+    columns: &mut Vec<AttributeColumn>,
+    trace_attributes: &Attributes,
+    event_attributes: impl Iterator<Item = &'a Attributes>,
+) {
+    let trace_attributes = trace_attributes.iter().map(|attribute| (attribute, true));
+    let event_attributes = event_attributes.flatten().map(|attribute| (attribute, false));
+    for (attribute, is_trace_attribute) in trace_attributes.chain(event_attributes) {
+        let Some(value_type) = ValueType::of(&attribute.value) else {
+            continue;
+        };
+        match columns.iter_mut().find(|column| column.key == attribute.key) {
+            Some(column) => {
+                column.value_type = column.value_type.widen(value_type);
+                column.is_trace_attribute &= is_trace_attribute;
+            }
+            None => columns.push(AttributeColumn {
+                key: attribute.key.clone(),
+                value_type,
+                is_trace_attribute,
+            }),
+        }
+    }
+}
+
+/// One event of the log as a row of the file.
+struct Row {
+    case: String,
+    variant: String,
+    activity: String,
+    resource: String,
+    start_time: Option<Timestamp>,
+
+    /// The end time of the interval shape, or the timestamp of the lifecycle and atomic shapes.
+    time: Option<Timestamp>,
+    lifecycle: String,
+    completion: &'static str,
+    scheduled_time: Option<Timestamp>,
+
+    /// The suspensions of the interval shape, see [`interval::format_suspensions`].
+    suspensions: String,
+
+    /// The processing time of the interval shape in seconds.
+    processing_time: Option<f64>,
+    attributes: Vec<Option<AttributeValue>>,
+}
+
+/// Writes traces to a Parquet file in one of the CSV shapes, with the column names of the mapping.
+///
+/// Timestamps are UTC timestamps with millisecond precision, the activity, resource, variant and lifecycle columns
+/// are dictionary-encoded strings, and every extra attribute has a typed column. The lifecycle details of the
+/// interval shape are a dictionary-encoded completion, a scheduled timestamp, the suspensions as text and the
+/// processing time in seconds.
+/// Rows are buffered and written one row group at a time, so memory use is bounded by the row group size.
+pub struct ParquetWriter<W: Write> {
+    /// Holds the bytes of the row group being written until they are copied to `output`. The Arrow writer needs an
+    /// output that is `Send`, which the `dyn Write` outputs of [`crate::format::EventLogWriter`] are not, and the
+    /// buffer is cleared after every row group, so it never holds more than one.
+    writer: ArrowWriter<Vec<u8>>,
+    output: W,
+    schema: SchemaRef,
+    columns: EventLogColumns,
+    details: DetailColumns,
+    attribute_columns: Vec<AttributeColumn>,
+    row_group_size: usize,
+    rows: Vec<Row>,
+}
+
+impl<W: Write> ParquetWriter<W> {
+    /// Creates a writer with the given extra columns after the mapped ones. Attributes not listed there are not
+    /// written. Variant and resource columns are left out if the mapping has none, and the columns of lifecycle
+    /// details unless the shape is the interval shape.
+    pub fn new(
+        output: W,
+        columns: EventLogColumns,
+        details: DetailColumns,
+        mut attribute_columns: Vec<AttributeColumn>,
+        row_group_size: usize,
+    ) -> Result<ParquetWriter<W>> {
+        let row_group_size = row_group_size.max(1);
+        let details = if columns.shape == CsvShape::Interval {
+            details
+        } else {
+            DetailColumns::default()
+        };
+        let names = details.names();
+        attribute_columns.retain(|column| !names.contains(&column.key.as_str()));
+        let mut fields = vec![Field::new(&columns.case, DataType::Utf8, false)];
+        if let Some(variant) = &columns.variant {
+            fields.push(Field::new(variant, dictionary_type(), true));
+        }
+        fields.push(Field::new(&columns.activity, dictionary_type(), false));
+        if let Some(resource) = &columns.resource {
+            fields.push(Field::new(resource, dictionary_type(), true));
+        }
+        match columns.shape {
+            CsvShape::Interval => {
+                fields.push(Field::new(&columns.start_time, timestamp_type(), false));
+                fields.push(Field::new(&columns.end_time, timestamp_type(), false));
+                if details.completion {
+                    fields.push(Field::new(COMPLETION_COLUMN, dictionary_type(), false));
+                }
+                if details.scheduled_time {
+                    fields.push(Field::new(SCHEDULED_TIME_COLUMN, timestamp_type(), true));
+                }
+                if details.suspensions {
+                    fields.push(Field::new(SUSPENSIONS_COLUMN, DataType::Utf8, false));
+                    fields.push(Field::new(PROCESSING_TIME_COLUMN, DataType::Float64, false));
+                }
+            }
+            CsvShape::Lifecycle => {
+                fields.push(Field::new(&columns.timestamp, timestamp_type(), true));
+                fields.push(Field::new(&columns.lifecycle, dictionary_type(), true));
+            }
+            CsvShape::Atomic => fields.push(Field::new(&columns.timestamp, timestamp_type(), true)),
+        }
+        for column in &attribute_columns {
+            let mut field = Field::new(
+                columns.attribute_column(&column.key),
+                column.value_type.data_type(),
+                true,
+            );
+            if column.is_trace_attribute {
+                field = field.with_metadata(HashMap::from([(SCOPE.to_string(), "trace".to_string())]));
+            }
+            fields.push(field);
+        }
+
+        let schema = Arc::new(Schema::new(fields));
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .set_max_row_group_size(row_group_size)
+            .build();
+        Ok(ParquetWriter {
+            writer: ArrowWriter::try_new(Vec::new(), schema.clone(), Some(properties))?,
+            output,
+            schema,
+            details,
+            columns,
+            attribute_columns,
+            row_group_size,
+            rows: Vec::new(),
+        })
+    }
+
+    /// Writes a trace as one row per activity instance. The writer has to have the interval shape.
+    pub fn write_interval_trace(&mut self, trace: &interval::Trace) -> Result<()> {
+        for event in &trace.events {
+            let row = Row {
+                case: trace.case.clone(),
+                variant: trace.variant.clone(),
+                activity: event.activity.clone(),
+                resource: event.resource.clone(),
+                start_time: Some(event.start_time),
+                time: Some(event.end_time),
+                lifecycle: String::new(),
+                completion: event.completion.lifecycle_transition(),
+                scheduled_time: event.scheduled_time,
+                suspensions: interval::format_suspensions(&event.suspensions),
+                processing_time: Some(event.processing_time().num_milliseconds() as f64 / 1000.0),
+                attributes: self.attribute_values(&event.attributes, &trace.attributes),
+            };
+            self.push(row)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a trace as one row per lifecycle event. The atomic shape only keeps `complete` events and events
+    /// without a transition.
+    pub fn write_lifecycle_trace(&mut self, trace: &lifecycle::Trace) -> Result<()> {
+        for event in &trace.events {
+            if self.columns.shape == CsvShape::Atomic && !event.is_complete() {
+                continue;
+            }
+            let row = Row {
+                case: trace.case.clone(),
+                variant: trace.variant.clone(),
+                activity: event.activity.clone(),
+                resource: event.resource.clone(),
+                start_time: None,
+                time: event.timestamp,
+                lifecycle: event.lifecycle.clone(),
+                completion: "",
+                scheduled_time: None,
+                suspensions: String::new(),
+                processing_time: None,
+                attributes: self.attribute_values(&event.attributes, &trace.attributes),
+            };
+            self.push(row)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered rows and the file footer, and returns the output, which still has to be flushed.
+    pub fn finish(mut self) -> Result<W> {
+        self.write_row_group()?;
+        let tail = self.writer.into_inner()?;
+        self.output.write_all(&tail)?;
+        Ok(self.output)
+    }
+
+    fn attribute_values(
+        &self,
+        event_attributes: &Attributes,
+        trace_attributes: &Attributes,
+    ) -> Vec<Option<AttributeValue>> {
+        self.attribute_columns
+            .iter()
+            .map(|column| {
+                let attribute = event_attributes
+                    .get(&column.key)
+                    .or_else(|| trace_attributes.get(&column.key));
+                attribute.map(|attribute| attribute.value.clone())
+            })
+            .collect()
+    }
+
+    fn push(&mut self, row: Row) -> Result<()> {
+        self.rows.push(row);
+        if self.rows.len() >= self.row_group_size {
+            self.write_row_group()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered rows as a row group and copies its bytes to the output.
+    fn write_row_group(&mut self) -> Result<()> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+
+        let rows = std::mem::take(&mut self.rows);
+        let dictionary = |value: fn(&Row) -> &str| -> ArrayRef {
+            let values = rows
+                .iter()
+                .map(|row| Some(value(row)).filter(|value| !value.is_empty()));
+            Arc::new(values.collect::<DictionaryArray<Int32Type>>())
+        };
+        let timestamps = |time: fn(&Row) -> Option<Timestamp>| -> ArrayRef {
+            let values = rows.iter().map(|row| time(row).map(|time| time.timestamp_millis()));
+            Arc::new(values.collect::<TimestampMillisecondArray>().with_timezone("UTC"))
+        };
+
+        let mut arrays: Vec<ArrayRef> = vec![Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|row| row.case.as_str()),
+        ))];
+        if self.columns.variant.is_some() {
+            arrays.push(dictionary(|row| &row.variant));
+        }
+        arrays.push(Arc::new(
+            rows.iter()
+                .map(|row| row.activity.as_str())
+                .collect::<DictionaryArray<Int32Type>>(),
+        ));
+        if self.columns.resource.is_some() {
+            arrays.push(dictionary(|row| &row.resource));
+        }
+        match self.columns.shape {
+            CsvShape::Interval => {
+                arrays.push(timestamps(|row| row.start_time));
+                arrays.push(timestamps(|row| row.time));
+                if self.details.completion {
+                    arrays.push(dictionary(|row| row.completion));
+                }
+                if self.details.scheduled_time {
+                    arrays.push(timestamps(|row| row.scheduled_time));
+                }
+                if self.details.suspensions {
+                    let values = rows.iter().map(|row| Some(row.suspensions.as_str()));
+                    arrays.push(Arc::new(values.collect::<StringArray>()));
+                    let values = rows.iter().map(|row| row.processing_time);
+                    arrays.push(Arc::new(values.collect::<Float64Array>()));
+                }
+            }
+            CsvShape::Lifecycle => {
+                arrays.push(timestamps(|row| row.time));
+                arrays.push(dictionary(|row| &row.lifecycle));
+            }
+            CsvShape::Atomic => arrays.push(timestamps(|row| row.time)),
+        }
+        for (index, column) in self.attribute_columns.iter().enumerate() {
+            let values = rows.iter().map(|row| row.attributes[index].as_ref());
+            arrays.push(attribute_array(column.value_type, values));
+        }
+
+        let batch = RecordBatch::try_new(self.schema.clone(), arrays)?;
+        self.writer.write(&batch)?;
+        self.writer.flush()?;
+        self.output.write_all(self.writer.inner())?;
+        self.writer.inner_mut().clear();
+        Ok(())
+    }
+}
+
+/// The values of an extra column as an array of its type. Values of a narrower type are converted.
+fn attribute_array<'a>(value_type: ValueType, values: impl Iterator<Item = Option<&'a AttributeValue>>) -> ArrayRef {
+    match value_type {
+        ValueType::String => Arc::new(
+            values
+                .map(|value| value.and_then(AttributeValue::to_xes_value))
+                .collect::<StringArray>(),
+        ),
+        ValueType::Int => Arc::new(
+            values
+                .map(|value| match value {
+                    Some(AttributeValue::Int(value)) => Some(*value),
+                    _ => None,
+                })
+                .collect::<Int64Array>(),
+        ),
+        ValueType::Float => Arc::new(
+            values
+                .map(|value| match value {
+                    Some(AttributeValue::Float(value)) => Some(*value),
+                    Some(AttributeValue::Int(value)) => Some(*value as f64),
+                    _ => None,
+                })
+                .collect::<Float64Array>(),
+        ),
+        ValueType::Boolean => Arc::new(
+            values
+                .map(|value| match value {
+                    Some(AttributeValue::Boolean(value)) => Some(*value),
+                    _ => None,
+                })
+                .collect::<BooleanArray>(),
+        ),
+        ValueType::Date => Arc::new(
+            values
+                .map(|value| match value {
+                    Some(AttributeValue::Date(value)) => Some(value.timestamp_millis()),
+                    _ => None,
+                })
+                .collect::<TimestampMillisecondArray>()
+                .with_timezone("UTC"),
+        ),
+    }
+}
+
+/// An extra column of a file being read, cast to the array type of its value type.
+struct ReadColumn {
+    key: String,
+    index: usize,
+    value_type: ValueType,
+    is_trace_attribute: bool,
+}
+
+/// A record batch with its mapped columns cast to strings and timestamps.
+struct Batch {
+    case: StringArray,
+    variant: Option<StringArray>,
+    activity: StringArray,
+    resource: Option<StringArray>,
+    start_time: Option<TimestampMillisecondArray>,
+    time: Option<TimestampMillisecondArray>,
+    lifecycle: Option<StringArray>,
+    completion: Option<StringArray>,
+    scheduled_time: Option<TimestampMillisecondArray>,
+    suspensions: Option<StringArray>,
+    processing_time: Option<Float64Array>,
+    attributes: Vec<ArrayRef>,
+}
+
+/// Reads the rows of a Parquet file one record batch at a time and yields them as lifecycle traces.
+///
+/// The columns are found by the names of the mapping, and the extra columns become attributes of their type. The
+/// columns of lifecycle details are read in the interval shape if the file has them, see [`DetailColumns`]. The
+/// rows of a case must be consecutive, as they are in written files, and a case whose rows are split is an error.
+pub struct ParquetTraceReader {
+    batches: ParquetRecordBatchReader,
+    columns: EventLogColumns,
+    indices: [Option<usize>; 11],
+    attribute_columns: Vec<ReadColumn>,
+    timezone: Option<Tz>,
+    batch: Option<Batch>,
+    row: usize,
+
+    /// Rows read so far, for error locations.
+    rows_read: usize,
+    current: Option<PendingTrace>,
+
+    /// Cases that were yielded, to tell rows of a case that are not consecutive.
+    finished_cases: HashSet<String>,
+    done: bool,
+}
+
+/// A trace whose rows are still being read.
+enum PendingTrace {
+    Interval(interval::Trace),
+    Lifecycle(lifecycle::Trace),
+}
+
+impl PendingTrace {
+    fn case(&self) -> &str {
+        match self {
+            PendingTrace::Interval(trace) => &trace.case,
+            PendingTrace::Lifecycle(trace) => &trace.case,
+        }
+    }
+
+    fn into_trace(self) -> lifecycle::Trace {
+        match self {
+            PendingTrace::Interval(trace) => conversion::interval_trace_to_lifecycle(&trace),
+            PendingTrace::Lifecycle(trace) => trace,
+        }
+    }
+}
+
+impl ParquetTraceReader {
+    /// Resolves the mapped columns in the schema of the file. Missing case, activity or timestamp columns are an
+    /// error, and variant and resource columns are optional.
+    pub fn new(
+        file: File,
+        columns: EventLogColumns,
+        timezone: Option<Tz>,
+        batch_size: usize,
+    ) -> Result<ParquetTraceReader> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file)?.with_batch_size(batch_size.max(1));
+        let schema = builder.schema().clone();
+        let index_of = |column: &str| schema.fields().iter().position(|field| field.name() == column);
+        let required = |column: &str| {
+            index_of(column).ok_or_else(|| Error::MissingColumn {
+                column: column.to_string(),
+            })
+        };
+
+        let (start_time, time, lifecycle) = match columns.shape {
+            CsvShape::Interval => (Some(required(&columns.start_time)?), required(&columns.end_time)?, None),
+            CsvShape::Lifecycle => (None, required(&columns.timestamp)?, Some(required(&columns.lifecycle)?)),
+            CsvShape::Atomic => (None, required(&columns.timestamp)?, None),
+        };
+        let detail = |column: &str| index_of(column).filter(|_| columns.shape == CsvShape::Interval);
+        let indices = [
+            Some(required(&columns.case)?),
+            columns.variant.as_deref().and_then(index_of),
+            Some(required(&columns.activity)?),
+            columns.resource.as_deref().and_then(index_of),
+            start_time,
+            Some(time),
+            lifecycle,
+            detail(COMPLETION_COLUMN),
+            detail(SCHEDULED_TIME_COLUMN),
+            detail(SUSPENSIONS_COLUMN),
+            detail(PROCESSING_TIME_COLUMN),
+        ];
+
+        let attribute_columns = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !indices.contains(&Some(*index)))
+            .filter_map(|(index, field)| {
+                Some(ReadColumn {
+                    key: columns.attribute_key(field.name()).to_string(),
+                    index,
+                    value_type: ValueType::of_data_type(field.data_type())?,
+                    is_trace_attribute: field.metadata().get(SCOPE).is_some_and(|scope| scope == "trace"),
+                })
+            })
+            .collect();
+
+        Ok(ParquetTraceReader {
+            batches: builder.build()?,
+            columns,
+            indices,
+            attribute_columns,
+            timezone,
+            batch: None,
+            row: 0,
+            rows_read: 0,
+            current: None,
+            finished_cases: HashSet::new(),
+            done: false,
+        })
+    }
+
+    /// Casts the columns of a record batch to the array types they are read as.
+    fn cast_batch(&self, batch: &RecordBatch) -> Result<Batch> {
+        let strings = |index: Option<usize>| -> Result<Option<StringArray>> {
+            index
+                .map(|index| {
+                    let array = arrow_cast::cast(batch.column(index), &DataType::Utf8)?;
+                    Ok(array.as_any().downcast_ref::<StringArray>().unwrap().clone())
+                })
+                .transpose()
+        };
+        let timestamps = |index: Option<usize>| -> Result<Option<TimestampMillisecondArray>> {
+            index
+                .map(|index| {
+                    let array = arrow_cast::cast(batch.column(index), &timestamp_type())?;
+                    Ok(array
+                        .as_any()
+                        .downcast_ref::<TimestampMillisecondArray>()
+                        .unwrap()
+                        .clone())
+                })
+                .transpose()
+        };
+        let seconds = |index: Option<usize>| -> Result<Option<Float64Array>> {
+            index
+                .map(|index| {
+                    let array = arrow_cast::cast(batch.column(index), &DataType::Float64)?;
+                    Ok(array.as_any().downcast_ref::<Float64Array>().unwrap().clone())
+                })
+                .transpose()
+        };
+        let [case, variant, activity, resource, start_time, time, lifecycle, completion, scheduled_time, ..] = self.indices;
+        let [.., suspensions, processing_time] = self.indices;
+
+        Ok(Batch {
+            case: strings(case)?.unwrap(),
+            variant: strings(variant)?,
+            activity: strings(activity)?.unwrap(),
+            resource: strings(resource)?,
+            start_time: timestamps(start_time)?,
+            time: timestamps(time)?,
+            lifecycle: strings(lifecycle)?,
+            completion: strings(completion)?,
+            scheduled_time: timestamps(scheduled_time)?,
+            suspensions: strings(suspensions)?,
+            processing_time: seconds(processing_time)?,
+            attributes: self
+                .attribute_columns
+                .iter()
+                .map(|column| arrow_cast::cast(batch.column(column.index), &column.value_type.data_type()))
+                .collect::<std::result::Result<_, _>>()?,
+        })
+    }
+
+    fn timestamp(&self, millis: i64) -> Option<Timestamp> {
+        let timestamp = Utc.timestamp_millis_opt(millis).single()?;
+        Some(match self.timezone {
+            Some(timezone) => timestamp.with_timezone(&timezone).fixed_offset(),
+            None => timestamp.fixed_offset(),
+        })
+    }
+
+    fn attribute(&self, column: &ReadColumn, array: &ArrayRef, row: usize) -> Option<Attribute> {
+        if array.is_null(row) {
+            return None;
+        }
+        let array = array.as_any();
+        let value = match column.value_type {
+            ValueType::String => AttributeValue::String(array.downcast_ref::<StringArray>()?.value(row).to_string()),
+            ValueType::Int => AttributeValue::Int(array.downcast_ref::<Int64Array>()?.value(row)),
+            ValueType::Float => AttributeValue::Float(array.downcast_ref::<Float64Array>()?.value(row)),
+            ValueType::Boolean => AttributeValue::Boolean(array.downcast_ref::<BooleanArray>()?.value(row)),
+            ValueType::Date => {
+                AttributeValue::Date(self.timestamp(array.downcast_ref::<TimestampMillisecondArray>()?.value(row))?)
+            }
+        };
+        Some(Attribute::new(&column.key, value))
+    }
+
+    /// The attributes of a row, either those of its trace or those of its event.
+    fn row_attributes(&self, batch: &Batch, row: usize, trace_attributes: bool) -> Attributes {
+        let mut attributes = Attributes::new();
+        for (column, array) in self.attribute_columns.iter().zip(&batch.attributes) {
+            if column.is_trace_attribute == trace_attributes {
+                if let Some(attribute) = self.attribute(column, array, row) {
+                    attributes.insert(attribute);
+                }
+            }
+        }
+        attributes
+    }
+
+    /// The timestamp of a row. Null timestamps are only allowed in the lifecycle and atomic shapes.
+    fn row_timestamp(
+        &self,
+        array: &Option<TimestampMillisecondArray>,
+        row: usize,
+        column: &str,
+    ) -> Result<Option<Timestamp>> {
+        let millis = array
+            .as_ref()
+            .filter(|array| array.is_valid(row))
+            .map(|array| array.value(row));
+        match millis.map(|millis| self.timestamp(millis)) {
+            Some(Some(timestamp)) => Ok(Some(timestamp)),
+            None if self.columns.shape != CsvShape::Interval => Ok(None),
+            _ => Err(Error::InvalidTimestamp {
+                value: millis.map(|millis| millis.to_string()),
+                location: format!("row {}, column '{column}'", self.rows_read),
+            }),
+        }
+    }
+
+    /// The error for a value of a column of lifecycle details that does not parse or is out of range.
+    fn invalid_value(&self, column: &str, value: &str) -> Error {
+        Error::InvalidAttribute {
+            key: column.to_string(),
+            value: value.to_string(),
+            location: format!("row {}", self.rows_read),
+        }
+    }
+
+    /// Adds the row to the current trace, or starts a new trace and returns the finished one.
+    fn read_row(&mut self, batch: &Batch, row: usize) -> Result<Option<lifecycle::Trace>> {
+        self.rows_read += 1;
+        let string = |array: &Option<StringArray>| match array {
+            Some(array) if array.is_valid(row) => array.value(row).to_string(),
+            _ => String::new(),
+        };
+
+        let case = batch.case.value(row);
+        let finished = match &self.current {
+            Some(current) if current.case() != case => self.current.take(),
+            _ => None,
+        };
+        if let Some(finished) = &finished {
+            self.finished_cases.insert(finished.case().to_string());
+        }
+        if self.current.is_none() {
+            if self.finished_cases.contains(case) {
+                return Err(ParquetError::General(format!(
+                    "rows of case '{case}' are not consecutive, at row {}",
+                    self.rows_read
+                ))
+                .into());
+            }
+            let variant = string(&batch.variant);
+            let attributes = self.row_attributes(batch, row, true);
+            self.current = Some(match self.columns.shape {
+                CsvShape::Interval => PendingTrace::Interval(interval::Trace {
+                    case: case.to_string(),
+                    variant,
+                    events: Vec::new(),
+                    attributes,
+                }),
+                _ => PendingTrace::Lifecycle(lifecycle::Trace {
+                    case: case.to_string(),
+                    variant,
+                    events: Vec::new(),
+                    attributes,
+                }),
+            });
+        }
+
+        let activity = batch.activity.value(row).to_string();
+        let resource = string(&batch.resource);
+        let attributes = self.row_attributes(batch, row, false);
+        match self.columns.shape {
+            CsvShape::Interval => {
+                let completion = string(&batch.completion);
+                let mut event = interval::Event {
+                    activity,
+                    resource,
+                    start_time: self
+                        .row_timestamp(&batch.start_time, row, &self.columns.start_time)?
+                        .unwrap(),
+                    end_time: self.row_timestamp(&batch.time, row, &self.columns.end_time)?.unwrap(),
+                    scheduled_time: batch
+                        .scheduled_time
+                        .as_ref()
+                        .filter(|array| array.is_valid(row))
+                        .and_then(|array| self.timestamp(array.value(row))),
+                    completion: Completion::from_lifecycle_transition(&completion)
+                        .ok_or_else(|| self.invalid_value(COMPLETION_COLUMN, &completion))?,
+                    attributes,
+                    ..Default::default()
+                };
+                if batch.suspensions.is_some() {
+                    let suspensions = string(&batch.suspensions);
+                    event.suspensions = event
+                        .parse_suspensions(&suspensions)
+                        .ok_or_else(|| self.invalid_value(SUSPENSIONS_COLUMN, &suspensions))?;
+                }
+                if let Some(array) = batch.processing_time.as_ref().filter(|array| array.is_valid(row)) {
+                    let seconds = array.value(row);
+                    if event.checked_processing_time(seconds).is_none() {
+                        return Err(self.invalid_value(PROCESSING_TIME_COLUMN, &seconds.to_string()));
+                    }
+                    if batch.suspensions.is_none() {
+                        let attribute = Attribute::new(PROCESSING_TIME_COLUMN, AttributeValue::Float(seconds));
+                        event.attributes.insert(attribute);
+                    }
+                }
+                if let Some(PendingTrace::Interval(trace)) = &mut self.current {
+                    trace.events.push(event);
+                }
+            }
+            _ => {
+                let event = lifecycle::Event {
+                    activity,
+                    resource,
+                    timestamp: self.row_timestamp(&batch.time, row, &self.columns.timestamp)?,
+                    lifecycle: string(&batch.lifecycle),
+                    attributes,
+                };
+                if let Some(PendingTrace::Lifecycle(trace)) = &mut self.current {
+                    trace.events.push(event);
+                }
+            }
+        }
+        Ok(finished.map(PendingTrace::into_trace))
+    }
+
+    fn next_trace(&mut self) -> Result<Option<lifecycle::Trace>> {
+        loop {
+            let batch = match self.batch.take() {
+                Some(batch) if self.row < batch.case.len() => batch,
+                _ => match self.batches.next() {
+                    Some(batch) => {
+                        self.row = 0;
+                        self.cast_batch(&batch?)?
+                    }
+                    None => return Ok(self.current.take().map(PendingTrace::into_trace)),
+                },
+            };
+            let row = self.row;
+            self.row += 1;
+            let finished = self.read_row(&batch, row);
+            self.batch = Some(batch);
+            if let Some(trace) = finished? {
+                return Ok(Some(trace));
+            }
+        }
+    }
+}
+
+impl Iterator for ParquetTraceReader {
+    type Item = Result<lifecycle::Trace>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.next_trace() {
+            Ok(Some(trace)) => Some(Ok(trace)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// The column names of a Parquet file, for matching them like a CSV header.
+pub fn read_field_names(file: File) -> Result<csv::StringRecord> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+    Ok(builder
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().as_str())
+        .collect())
+}
+
+/// Whether the bytes start a Parquet file.
+pub fn is_parquet(start: &[u8]) -> bool {
+    start.starts_with(b"PAR1")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Seek, SeekFrom};
+    use std::path::PathBuf;
+
+    use chrono::DateTime;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    use crate::config::Config;
+
+    use super::*;
+
+    fn test_log() -> lifecycle::EventLog {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("event_logs");
+        path.push("Production.xes");
+        lifecycle::parse_file(&path, false).unwrap()
+    }
+
+    fn time(value: &str) -> Timestamp {
+        DateTime::parse_from_rfc3339(value).unwrap()
+    }
+
+    /// Writes the traces with the writer and returns the file, rewound for reading.
+    fn write_file(write: impl FnOnce(&mut File) -> Result<()>) -> File {
+        let mut file = tempfile::tempfile().unwrap();
+        write(&mut file).unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_parquet_interval_round_trip() {
+        let mut trace = interval::Trace {
+            case: "1".to_string(),
+            variant: "2".to_string(),
+            events: Vec::new(),
+            attributes: Attributes::new(),
+        };
+        trace
+            .attributes
+            .insert(Attribute::new("priority", AttributeValue::String("high".to_string())));
+        let mut event = interval::Event {
+            activity: "Turning & Milling".to_string(),
+            resource: "Machine 4".to_string(),
+            start_time: time("2012-01-29T23:24:00+01:00"),
+            end_time: time("2012-01-30T05:43:00.123+01:00"),
+            ..Default::default()
+        };
+        event.attributes.insert(Attribute::new("cost", AttributeValue::Int(12)));
+        event
+            .attributes
+            .insert(Attribute::new("urgent", AttributeValue::Boolean(true)));
+        event.attributes.insert(Attribute::new(
+            "due",
+            AttributeValue::Date(time("2012-02-01T00:00:00Z")),
+        ));
+        trace.events.push(event.clone());
+        event
+            .attributes
+            .insert(Attribute::new("cost", AttributeValue::Float(0.5)));
+        event
+            .attributes
+            .insert(Attribute::new("urgent", AttributeValue::Boolean(false)));
+        trace.events.push(event);
+
+        let columns = Config::default().event_log_columns(None);
+        let mut attribute_columns = Vec::new();
+        add_interval_attribute_columns(&mut attribute_columns, &trace);
+        assert_eq!(attribute_columns[0].key, "priority");
+        assert!(attribute_columns[0].is_trace_attribute);
+        assert_eq!(
+            attribute_columns
+                .iter()
+                .find(|column| column.key == "cost")
+                .unwrap()
+                .value_type,
+            ValueType::Float
+        );
+
+        let file = write_file(|file| {
+            let mut writer =
+                ParquetWriter::new(file, columns.clone(), DetailColumns::default(), attribute_columns, 16)?;
+            writer.write_interval_trace(&trace)?;
+            writer.finish().map(|_| ())
+        });
+        let traces = ParquetTraceReader::new(file, columns, None, 16)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(traces.len(), 1);
+        let read = &traces[0];
+        assert_eq!((read.case.as_str(), read.variant.as_str()), ("1", "2"));
+        assert_eq!(
+            read.attributes.get("priority").unwrap().value,
+            AttributeValue::String("high".to_string())
+        );
+        assert_eq!(read.events.len(), 4);
+        let start = &read.events[0];
+        assert_eq!(start.activity, "Turning & Milling");
+        assert_eq!(start.resource, "Machine 4");
+        assert_eq!(start.lifecycle, "start");
+        assert_eq!(start.timestamp, Some(time("2012-01-29T22:24:00Z")));
+        assert_eq!(start.attributes.get("cost").unwrap().value, AttributeValue::Float(12.0));
+        assert_eq!(
+            start.attributes.get("urgent").unwrap().value,
+            AttributeValue::Boolean(true)
+        );
+        assert_eq!(
+            start.attributes.get("due").unwrap().value,
+            AttributeValue::Date(time("2012-02-01T00:00:00Z"))
+        );
+        assert!(start.attributes.get("priority").is_none());
+        let complete = read.events.iter().find(|event| event.lifecycle == "complete").unwrap();
+        assert_eq!(complete.timestamp, Some(time("2012-01-30T04:43:00.123Z")));
+    }
+
+    #[test]
+    fn test_parquet_lifecycle_details() {
+        let mut event = interval::Event {
+            activity: "Lapping".to_string(),
+            resource: "Machine 1".to_string(),
+            start_time: time("2012-01-30T08:00:00Z"),
+            end_time: time("2012-01-30T10:00:00Z"),
+            scheduled_time: Some(time("2012-01-30T07:30:00Z")),
+            completion: Completion::Aborted,
+            ..Default::default()
+        };
+        event.suspensions.push(interval::Suspension {
+            suspend_time: time("2012-01-30T09:00:00Z"),
+            resume_time: time("2012-01-30T09:30:00Z"),
+        });
+        let trace = interval::Trace {
+            case: "1".to_string(),
+            variant: "1".to_string(),
+            events: vec![event],
+            attributes: Attributes::new(),
+        };
+        let mut details = DetailColumns::default();
+        details.add(&trace);
+
+        let columns = Config::default().event_log_columns(None);
+        let file = write_file(|file| {
+            let mut writer = ParquetWriter::new(file, columns.clone(), details, Vec::new(), 16)?;
+            writer.write_interval_trace(&trace)?;
+            writer.finish().map(|_| ())
+        });
+        let headers = read_field_names(file.try_clone().unwrap()).unwrap();
+        assert_eq!(
+            headers.iter().skip(6).collect::<Vec<_>>(),
+            [
+                COMPLETION_COLUMN,
+                SCHEDULED_TIME_COLUMN,
+                SUSPENSIONS_COLUMN,
+                PROCESSING_TIME_COLUMN
+            ]
+        );
+
+        let traces = ParquetTraceReader::new(file, columns, None, 16)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let transitions = traces[0]
+            .events
+            .iter()
+            .map(|event| (event.lifecycle.as_str(), event.timestamp.unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            transitions,
+            [
+                ("schedule", time("2012-01-30T07:30:00Z")),
+                ("start", time("2012-01-30T08:00:00Z")),
+                ("suspend", time("2012-01-30T09:00:00Z")),
+                ("resume", time("2012-01-30T09:30:00Z")),
+                ("ate_abort", time("2012-01-30T10:00:00Z")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parquet_invalid_processing_time() {
+        let columns = Config::default().event_log_columns(None);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("case", DataType::Utf8, false),
+            Field::new("activity", DataType::Utf8, false),
+            Field::new("start_time", timestamp_type(), false),
+            Field::new("end_time", timestamp_type(), false),
+            Field::new(PROCESSING_TIME_COLUMN, DataType::Float64, false),
+        ]));
+        let start = time("2012-01-30T09:00:00Z").timestamp_millis();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["1", "1"])),
+                Arc::new(StringArray::from(vec!["A", "B"])),
+                Arc::new(TimestampMillisecondArray::from(vec![start, start]).with_timezone("UTC")),
+                Arc::new(
+                    TimestampMillisecondArray::from(vec![start + 3_600_000, start + 3_600_000]).with_timezone("UTC"),
+                ),
+                Arc::new(Float64Array::from(vec![1800.0, -600.0])),
+            ],
+        )
+        .unwrap();
+        let file = write_file(|file| {
+            let mut writer = ArrowWriter::try_new(file, schema, None)?;
+            writer.write(&batch)?;
+            writer.close()?;
+            Ok(())
+        });
+
+        let error = ParquetTraceReader::new(file, columns, None, 16)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(error, Error::InvalidAttribute { key, value, location }
+            if key == PROCESSING_TIME_COLUMN && value == "-600" && location == "row 2"));
+    }
+
+    #[test]
+    fn test_parquet_row_groups() {
+        let log = test_log();
+        let columns = EventLogColumns {
+            shape: CsvShape::Lifecycle,
+            ..Config::default().event_log_columns(None)
+        };
+        let mut attribute_columns = Vec::new();
+        for trace in &log.traces {
+            add_lifecycle_attribute_columns(&mut attribute_columns, trace);
+        }
+
+        let file = write_file(|file| {
+            let mut writer =
+                ParquetWriter::new(file, columns.clone(), DetailColumns::default(), attribute_columns, 100)?;
+            for trace in &log.traces {
+                writer.write_lifecycle_trace(trace)?;
+            }
+            writer.finish().map(|_| ())
+        });
+
+        let metadata = SerializedFileReader::new(file.try_clone().unwrap())
+            .unwrap()
+            .metadata()
+            .clone();
+        let rows = log.traces.iter().map(|trace| trace.events.len()).sum::<usize>();
+        assert_eq!(metadata.num_row_groups(), rows.div_ceil(100));
+        assert!(metadata
+            .row_groups()
+            .iter()
+            .all(|row_group| row_group.num_rows() <= 100));
+
+        let headers = read_field_names(file.try_clone().unwrap()).unwrap();
+        assert_eq!(
+            headers.iter().take(5).collect::<Vec<_>>(),
+            ["case", "variant", "activity", "resource", "timestamp"]
+        );
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file.try_clone().unwrap()).unwrap();
+        assert_eq!(builder.schema().field(2).data_type(), &dictionary_type());
+        assert_eq!(builder.schema().field(4).data_type(), &timestamp_type());
+
+        let traces = ParquetTraceReader::new(file, columns, None, 64)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(traces.len(), log.traces.len());
+        for (read, original) in traces.iter().zip(&log.traces) {
+            assert_eq!(read.case, original.case);
+            assert_eq!(read.events.len(), original.events.len());
+            assert_eq!(read.events[0].activity, original.events[0].activity);
+            assert_eq!(read.events[0].lifecycle, original.events[0].lifecycle);
+            assert_eq!(read.events[0].timestamp, original.events[0].timestamp);
+        }
+    }
+
+    #[test]
+    fn test_parquet_interleaved_cases() {
+        let log = test_log();
+        let columns = EventLogColumns {
+            shape: CsvShape::Lifecycle,
+            ..Config::default().event_log_columns(None)
+        };
+        let file = write_file(|file| {
+            let mut writer = ParquetWriter::new(file, columns.clone(), DetailColumns::default(), Vec::new(), 16)?;
+            for trace in [&log.traces[0], &log.traces[1], &log.traces[0]] {
+                writer.write_lifecycle_trace(trace)?;
+            }
+            writer.finish().map(|_| ())
+        });
+
+        let traces = ParquetTraceReader::new(file, columns, None, 16)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(traces.len(), 2);
+        assert!(traces[0].is_ok());
+        let error = traces[1].as_ref().unwrap_err().to_string();
+        assert!(error.contains(&format!("rows of case '{}' are not consecutive", log.traces[0].case)));
+    }
+
+    #[test]
+    fn test_parquet_missing_column() {
+        let columns = Config::default().event_log_columns(None);
+        let file = write_file(|file| {
+            ParquetWriter::new(file, columns, DetailColumns::default(), Vec::new(), 16)?
+                .finish()
+                .map(|_| ())
+        });
+        let columns = EventLogColumns {
+            activity: "Activity".to_string(),
+            ..Config::default().event_log_columns(None)
+        };
+
+        let error = ParquetTraceReader::new(file, columns, None, 16).err().unwrap();
+        assert!(matches!(error, Error::MissingColumn { column } if column == "Activity"));
+    }
+}
@@ -0,0 +1,267 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, Offset, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{Error, Result};
+
+pub type Timestamp = DateTime<FixedOffset>;
+
+/// A way timestamps can be written in an input log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// ISO 8601 date and time with `T` or a space as separator, optional fractional seconds and optional offset,
+    /// e.g., `2022-01-03T08:10:00.000+02:00` or `2022-01-03 08:10:00`. Also accepts a bare date.
+    Iso8601,
+
+    /// Seconds since the Unix epoch.
+    EpochSeconds,
+
+    /// Milliseconds since the Unix epoch.
+    EpochMillis,
+
+    /// A `strftime`-like pattern as understood by chrono, e.g., `%d.%m.%Y %H:%M`.
+    Custom(String),
+}
+
+impl FromStr for TimestampFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "iso8601" => Ok(TimestampFormat::Iso8601),
+            "epoch-seconds" => Ok(TimestampFormat::EpochSeconds),
+            "epoch-millis" => Ok(TimestampFormat::EpochMillis),
+            pattern if pattern.contains('%') => Ok(TimestampFormat::Custom(pattern.to_string())),
+            _ => Err(format!(
+                "unknown timestamp format '{s}', expected iso8601, epoch-seconds, epoch-millis or a pattern like %Y-%m-%d"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TimestampFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimestampFormat::Iso8601 => write!(f, "iso8601"),
+            TimestampFormat::EpochSeconds => write!(f, "epoch-seconds"),
+            TimestampFormat::EpochMillis => write!(f, "epoch-millis"),
+            TimestampFormat::Custom(pattern) => write!(f, "{pattern}"),
+        }
+    }
+}
+
+/// Serialized as the name or pattern accepted by `FromStr`.
+impl Serialize for TimestampFormat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimestampFormat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+const ISO_8601_PATTERNS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
+/// A parsed timestamp before a timezone is assumed for values without an offset.
+enum ParsedTimestamp {
+    Offset(Timestamp),
+    Naive(NaiveDateTime),
+}
+
+impl TimestampFormat {
+    /// Parses a timestamp, taking values without an offset as UTC.
+    pub fn parse(&self, value: &str) -> Option<Timestamp> {
+        match self.parse_offset(value)? {
+            ParsedTimestamp::Offset(timestamp) => Some(timestamp),
+            ParsedTimestamp::Naive(timestamp) => Some(Utc.from_utc_datetime(&timestamp).into()),
+        }
+    }
+
+    fn parse_offset(&self, value: &str) -> Option<ParsedTimestamp> {
+        let value = value.trim();
+        match self {
+            TimestampFormat::Iso8601 => {
+                if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+                    return Some(ParsedTimestamp::Offset(timestamp));
+                }
+                for pattern in ISO_8601_PATTERNS {
+                    for offset in ["%:z", "%z"] {
+                        if let Ok(timestamp) = DateTime::parse_from_str(value, &format!("{pattern}{offset}")) {
+                            return Some(ParsedTimestamp::Offset(timestamp));
+                        }
+                    }
+                    if let Some(utc) = value.strip_suffix('Z') {
+                        if let Ok(timestamp) = NaiveDateTime::parse_from_str(utc, pattern) {
+                            return Some(ParsedTimestamp::Offset(Utc.from_utc_datetime(&timestamp).into()));
+                        }
+                    }
+                    if let Ok(timestamp) = NaiveDateTime::parse_from_str(value, pattern) {
+                        return Some(ParsedTimestamp::Naive(timestamp));
+                    }
+                }
+                let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+                Some(ParsedTimestamp::Naive(date.and_hms_opt(0, 0, 0)?))
+            }
+            TimestampFormat::EpochSeconds => {
+                let seconds = value.parse::<i64>().ok()?;
+                Some(ParsedTimestamp::Offset(Utc.timestamp_opt(seconds, 0).single()?.into()))
+            }
+            TimestampFormat::EpochMillis => {
+                let millis = value.parse::<i64>().ok()?;
+                Some(ParsedTimestamp::Offset(
+                    Utc.timestamp_millis_opt(millis).single()?.into(),
+                ))
+            }
+            TimestampFormat::Custom(pattern) => DateTime::parse_from_str(value, pattern)
+                .map(ParsedTimestamp::Offset)
+                .or_else(|_| NaiveDateTime::parse_from_str(value, pattern).map(ParsedTimestamp::Naive))
+                .ok(),
+        }
+    }
+}
+
+/// Parses timestamps by trying a list of formats in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampParser {
+    pub formats: Vec<TimestampFormat>,
+
+    /// Timezone of timestamps without an offset.
+    pub input_timezone: Tz,
+
+    /// Timezone every parsed timestamp is converted to. Offsets are kept as they are if not set.
+    pub output_timezone: Option<Tz>,
+}
+
+impl Default for TimestampParser {
+    fn default() -> Self {
+        TimestampParser {
+            formats: vec![TimestampFormat::Iso8601],
+            input_timezone: Tz::UTC,
+            output_timezone: None,
+        }
+    }
+}
+
+impl TimestampParser {
+    pub fn new(formats: Vec<TimestampFormat>) -> TimestampParser {
+        TimestampParser {
+            formats,
+            ..Default::default()
+        }
+    }
+
+    /// Parses a timestamp. `location` names the row or event for the error if the value cannot be parsed, or if
+    /// it is a local time that is ambiguous or skipped in the input timezone because of a DST change.
+    pub fn parse(&self, value: &str, location: impl FnOnce() -> String) -> Result<Timestamp> {
+        let parsed = self.formats.iter().find_map(|format| format.parse_offset(value));
+        let timestamp = match parsed {
+            Some(ParsedTimestamp::Offset(timestamp)) => timestamp,
+            Some(ParsedTimestamp::Naive(timestamp)) => match self.input_timezone.from_local_datetime(&timestamp) {
+                LocalResult::Single(timestamp) => timestamp.with_timezone(&timestamp.offset().fix()),
+                result => {
+                    return Err(Error::LocalTime {
+                        value: value.to_string(),
+                        timezone: self.input_timezone.name().to_string(),
+                        location: location(),
+                        ambiguous: matches!(result, LocalResult::Ambiguous(..)),
+                    })
+                }
+            },
+            None => {
+                return Err(Error::InvalidTimestamp {
+                    value: Some(value.to_string()),
+                    location: location(),
+                })
+            }
+        };
+
+        Ok(match self.output_timezone {
+            Some(timezone) => {
+                let timestamp = timestamp.with_timezone(&timezone);
+                timestamp.with_timezone(&timestamp.offset().fix())
+            }
+            None => timestamp,
+        })
+    }
+}
+
+/// Formats a timestamp as RFC 3339 with milliseconds, which is valid as an XES `date` value.
+pub fn format(timestamp: &Timestamp) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Millis, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_formats() {
+        let expected = DateTime::parse_from_rfc3339("2022-01-03T08:10:00+00:00").unwrap();
+        let cases = [
+            (TimestampFormat::Iso8601, "2022-01-03T08:10:00.000+00:00"),
+            (TimestampFormat::Iso8601, "2022-01-03T10:10:00+02:00"),
+            (TimestampFormat::Iso8601, "2022-01-03 08:10:00"),
+            (TimestampFormat::Iso8601, "2022-01-03 08:10:00.000Z"),
+            (TimestampFormat::EpochSeconds, "1641197400"),
+            (TimestampFormat::EpochMillis, "1641197400000"),
+            (
+                TimestampFormat::Custom("%d.%m.%Y %H:%M".to_string()),
+                "03.01.2022 08:10",
+            ),
+        ];
+
+        for (format, value) in cases {
+            assert_eq!(format.parse(value), Some(expected), "{format:?} {value}");
+        }
+        assert_eq!(TimestampFormat::Iso8601.parse("yesterday"), None);
+    }
+
+    #[test]
+    fn test_format() {
+        let timestamp = TimestampFormat::Iso8601.parse("2022-01-03 08:10:00").unwrap();
+
+        assert_eq!(format(&timestamp), "2022-01-03T08:10:00.000+00:00");
+    }
+
+    #[test]
+    fn test_timezones() {
+        let parser = TimestampParser {
+            input_timezone: chrono_tz::Europe::Berlin,
+            ..Default::default()
+        };
+        let location = || "line 2".to_string();
+
+        let winter = parser.parse("2022-01-03 09:10:00", location).unwrap();
+        let summer = parser.parse("2022-07-01 09:10:00", location).unwrap();
+        let with_offset = parser.parse("2022-01-03T09:10:00-05:00", location).unwrap();
+        assert_eq!(format(&winter), "2022-01-03T09:10:00.000+01:00");
+        assert_eq!(format(&summer), "2022-07-01T09:10:00.000+02:00");
+        assert_eq!(format(&with_offset), "2022-01-03T09:10:00.000-05:00");
+
+        assert!(matches!(
+            parser.parse("2022-10-30 02:30:00", location),
+            Err(Error::LocalTime { ambiguous: true, timezone, .. }) if timezone == "Europe/Berlin"
+        ));
+        assert!(matches!(
+            parser.parse("2022-03-27 02:30:00", location),
+            Err(Error::LocalTime { ambiguous: false, .. })
+        ));
+        assert!(matches!(
+            parser.parse("noon", location),
+            Err(Error::InvalidTimestamp { location, .. }) if location == "line 2"
+        ));
+
+        let parser = TimestampParser {
+            output_timezone: Some(Tz::UTC),
+            ..parser
+        };
+        let utc = parser.parse("2022-07-01 09:10:00", location).unwrap();
+        assert_eq!(format(&utc), "2022-07-01T07:10:00.000+00:00");
+    }
+}
@@ -1,2 +1,4 @@
 pub mod lifecycle;
 pub mod interval;
+pub mod attribute;
+pub mod header;
@@ -0,0 +1,316 @@
+use std::io::{BufRead, Write};
+
+use quick_xml::events::{BytesStart, Event as XmlEvent};
+use quick_xml::{Reader, Writer};
+
+use crate::error::{Error, Result};
+use crate::timestamp::{self, Timestamp, TimestampParser};
+
+/// Value of an XES attribute, one variant per attribute type of IEEE 1849-2016.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    String(String),
+    Date(Timestamp),
+    Int(i64),
+    Float(f64),
+    Boolean(bool),
+    Id(String),
+    List(Vec<Attribute>),
+    Container(Vec<Attribute>),
+}
+
+impl AttributeValue {
+    /// The XES element name of the attribute type.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            AttributeValue::String(_) => "string",
+            AttributeValue::Date(_) => "date",
+            AttributeValue::Int(_) => "int",
+            AttributeValue::Float(_) => "float",
+            AttributeValue::Boolean(_) => "boolean",
+            AttributeValue::Id(_) => "id",
+            AttributeValue::List(_) => "list",
+            AttributeValue::Container(_) => "container",
+        }
+    }
+
+    /// The value as written in the `value` XML attribute. Lists and containers have none.
+    pub fn to_xes_value(&self) -> Option<String> {
+        match self {
+            AttributeValue::String(v) | AttributeValue::Id(v) => Some(v.clone()),
+            AttributeValue::Date(v) => Some(timestamp::format(v)),
+            AttributeValue::Int(v) => Some(v.to_string()),
+            AttributeValue::Float(v) => Some(v.to_string()),
+            AttributeValue::Boolean(v) => Some(v.to_string()),
+            AttributeValue::List(_) | AttributeValue::Container(_) => None,
+        }
+    }
+
+    /// Whether this is a string or ID without any text, which writers leave out.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            AttributeValue::String(v) | AttributeValue::Id(v) => v.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+/// A keyed XES attribute. `children` holds nested meta-attributes, which any attribute may have.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub key: String,
+    pub value: AttributeValue,
+    pub children: Vec<Attribute>,
+}
+
+impl Attribute {
+    pub fn new(key: &str, value: AttributeValue) -> Attribute {
+        Attribute {
+            key: key.to_string(),
+            value,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Attributes keyed by name that keep their document order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attributes(Vec<Attribute>);
+
+impl Attributes {
+    pub fn new() -> Attributes {
+        Attributes(Vec::new())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Attribute> {
+        self.0.iter().find(|a| a.key == key)
+    }
+
+    /// Inserts the attribute, replacing one with the same key in place.
+    pub fn insert(&mut self, attribute: Attribute) {
+        match self.0.iter_mut().find(|a| a.key == attribute.key) {
+            Some(existing) => *existing = attribute,
+            None => self.0.push(attribute),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Attribute> {
+        let index = self.0.iter().position(|a| a.key == key)?;
+        Some(self.0.remove(index))
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Attribute> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a Attributes {
+    type Item = &'a Attribute;
+    type IntoIter = std::slice::Iter<'a, Attribute>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<Attribute> for Attributes {
+    fn from_iter<I: IntoIterator<Item = Attribute>>(iter: I) -> Self {
+        let mut attributes = Attributes::new();
+        for attribute in iter {
+            attributes.insert(attribute);
+        }
+        attributes
+    }
+}
+
+pub(crate) fn is_attribute_tag(name: &[u8]) -> bool {
+    matches!(
+        name,
+        b"string" | b"date" | b"int" | b"float" | b"boolean" | b"id" | b"list" | b"container"
+    )
+}
+
+/// Reads the next XML event, attaching the reader position to syntax errors.
+pub(crate) fn read_event<'b, R: BufRead>(reader: &mut Reader<R>, buf: &'b mut Vec<u8>) -> Result<XmlEvent<'b>> {
+    buf.clear();
+    reader.read_event_into(buf).map_err(|e| xml_error(reader, e))
+}
+
+pub(crate) fn xml_error<R>(reader: &Reader<R>, e: quick_xml::Error) -> Error {
+    match e {
+        quick_xml::Error::Io(e) => Error::Io(e),
+        source => Error::Xml {
+            position: Some(reader.buffer_position()),
+            source,
+        },
+    }
+}
+
+/// Reads the text content of an element up to its end tag.
+pub(crate) fn read_text<R: BufRead>(reader: &mut Reader<R>, buf: &mut Vec<u8>, tag: &[u8]) -> Result<String> {
+    let mut text = String::new();
+    loop {
+        match read_event(reader, buf)? {
+            XmlEvent::Text(e) => {
+                let e = e.into_owned();
+                text.push_str(&e.unescape().map_err(|e| xml_error(reader, e))?);
+            }
+            XmlEvent::CData(e) => text.push_str(&String::from_utf8_lossy(&e)),
+            XmlEvent::End(e) if e.name().as_ref() == tag => return Ok(text),
+            XmlEvent::Eof => {
+                let tag = String::from_utf8_lossy(tag).into_owned();
+                return Err(xml_error(reader, quick_xml::Error::UnexpectedEof(tag)));
+            }
+            _ => (),
+        }
+    }
+}
+
+/// The XML attributes of an element as keys and unescaped values.
+pub(crate) fn element_attributes<R>(reader: &Reader<R>, element: &BytesStart) -> Result<Vec<(String, String)>> {
+    let mut attributes = Vec::new();
+    for a in element.attributes() {
+        let a = a.map_err(|e| xml_error(reader, e.into()))?;
+        let key = String::from_utf8_lossy(a.key.as_ref()).into_owned();
+        let value = a.unescape_value().map_err(|e| xml_error(reader, e))?.into_owned();
+        attributes.push((key, value));
+    }
+    Ok(attributes)
+}
+
+/// Reads an attribute element. For a non-empty element, consumes everything up to its end tag.
+pub(crate) fn read_attribute<R: BufRead>(
+    reader: &mut Reader<R>,
+    buf: &mut Vec<u8>,
+    element: &BytesStart,
+    is_empty: bool,
+    timestamps: &TimestampParser,
+) -> Result<Attribute> {
+    let tag = element.name().as_ref().to_vec();
+    let mut key = String::new();
+    let mut value = String::new();
+    for a in element.attributes() {
+        let a = a.map_err(|e| xml_error(reader, e.into()))?;
+        match a.key.as_ref() {
+            b"key" => key = a.unescape_value().map_err(|e| xml_error(reader, e))?.into_owned(),
+            b"value" => value = a.unescape_value().map_err(|e| xml_error(reader, e))?.into_owned(),
+            _ => (),
+        }
+    }
+
+    let mut children = Vec::new();
+    let mut values = Vec::new();
+    if !is_empty {
+        let mut in_values = false;
+        loop {
+            match read_event(reader, buf)? {
+                XmlEvent::Start(e) if e.name().as_ref() == b"values" => in_values = true,
+                XmlEvent::End(e) if e.name().as_ref() == b"values" => in_values = false,
+                XmlEvent::Start(e) if is_attribute_tag(e.name().as_ref()) => {
+                    let e = e.into_owned();
+                    let child = read_attribute(reader, buf, &e, false, timestamps)?;
+                    if in_values {
+                        values.push(child);
+                    } else {
+                        children.push(child);
+                    }
+                }
+                XmlEvent::Empty(e) if is_attribute_tag(e.name().as_ref()) => {
+                    let e = e.into_owned();
+                    let child = read_attribute(reader, buf, &e, true, timestamps)?;
+                    if in_values {
+                        values.push(child);
+                    } else {
+                        children.push(child);
+                    }
+                }
+                XmlEvent::End(e) if e.name().as_ref() == tag.as_slice() => break,
+                XmlEvent::Eof => {
+                    return Err(xml_error(
+                        reader,
+                        quick_xml::Error::UnexpectedEof(String::from_utf8_lossy(&tag).into_owned()),
+                    ))
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let invalid = |value: &str| Error::InvalidAttribute {
+        key: key.clone(),
+        value: value.to_string(),
+        location: format!("byte {}", reader.buffer_position()),
+    };
+    let value = match tag.as_slice() {
+        b"string" => AttributeValue::String(value),
+        b"date" => {
+            let position = reader.buffer_position();
+            let timestamp = timestamps.parse(&value, || format!("byte {position}, attribute '{key}'"));
+            AttributeValue::Date(timestamp.map_err(|e| match e {
+                Error::InvalidTimestamp { .. } => invalid(&value),
+                e => e,
+            })?)
+        }
+        b"id" => AttributeValue::Id(value),
+        b"int" => AttributeValue::Int(value.trim().parse().map_err(|_| invalid(&value))?),
+        b"float" => AttributeValue::Float(value.trim().parse().map_err(|_| invalid(&value))?),
+        b"boolean" => AttributeValue::Boolean(value.trim().to_lowercase().parse().map_err(|_| invalid(&value))?),
+        b"list" => AttributeValue::List(values),
+        _ => AttributeValue::Container(std::mem::take(&mut children)),
+    };
+
+    Ok(Attribute { key, value, children })
+}
+
+/// Writes an attribute as the XES element of its type, with nested values and meta-attributes.
+pub(crate) fn write_attribute<W: Write>(writer: &mut Writer<W>, attribute: &Attribute) -> quick_xml::Result<()> {
+    let value = attribute.value.to_xes_value();
+    let mut element = writer
+        .create_element(attribute.value.tag())
+        .with_attribute(("key", attribute.key.as_str()));
+    if let Some(value) = value.as_deref() {
+        element = element.with_attribute(("value", value));
+    }
+
+    let nested = match &attribute.value {
+        AttributeValue::List(values) | AttributeValue::Container(values) => !values.is_empty(),
+        _ => false,
+    };
+    if !nested && attribute.children.is_empty() {
+        element.write_empty()?;
+        return Ok(());
+    }
+
+    element.write_inner_content(|w| {
+        match &attribute.value {
+            AttributeValue::List(values) => {
+                w.create_element("values").write_inner_content(|w| {
+                    for value in values {
+                        write_attribute(w, value)?;
+                    }
+                    Ok(())
+                })?;
+            }
+            AttributeValue::Container(values) => {
+                for value in values {
+                    write_attribute(w, value)?;
+                }
+            }
+            _ => (),
+        }
+        for child in &attribute.children {
+            write_attribute(w, child)?;
+        }
+        Ok(())
+    })?;
+
+    Ok(())
+}
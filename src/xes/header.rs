@@ -0,0 +1,207 @@
+use std::io::{BufRead, Write};
+
+use quick_xml::events::{BytesStart, Event as XmlEvent};
+use quick_xml::{Reader, Writer};
+
+use crate::error::Result;
+use crate::timestamp::TimestampParser;
+use crate::xes::attribute::{element_attributes, is_attribute_tag, read_attribute, read_event, write_attribute, xml_error};
+use crate::xes::attribute::{Attribute, Attributes};
+
+/// Log-level declarations that precede the traces of an XES log.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogHeader {
+    /// The `xes.version` of the `<log>` element.
+    pub version: Option<String>,
+
+    /// The `xes.features` of the `<log>` element.
+    pub features: Option<String>,
+
+    pub extensions: Vec<Extension>,
+
+    /// Default values of attributes every trace is guaranteed to have (`<global scope="trace">`).
+    pub trace_globals: Vec<Attribute>,
+
+    /// Default values of attributes every event is guaranteed to have (`<global scope="event">`).
+    pub event_globals: Vec<Attribute>,
+
+    pub classifiers: Vec<Classifier>,
+
+    /// Attributes of the log itself, e.g., its concept:name.
+    pub attributes: Attributes,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extension {
+    pub name: String,
+    pub prefix: String,
+    pub uri: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classifier {
+    pub name: String,
+
+    /// Space-separated attribute keys, as in the XES `keys` attribute.
+    pub keys: String,
+}
+
+impl Extension {
+    pub fn new(name: &str, prefix: &str, uri: &str) -> Extension {
+        Extension {
+            name: name.to_string(),
+            prefix: prefix.to_string(),
+            uri: uri.to_string(),
+        }
+    }
+
+    /// The Concept, Time, Lifecycle and Organizational extensions, which cover the attributes this crate writes.
+    pub fn standard() -> Vec<Extension> {
+        vec![
+            Extension::new("Concept", "concept", "http://www.xes-standard.org/concept.xesext"),
+            Extension::new("Time", "time", "http://www.xes-standard.org/time.xesext"),
+            Extension::new("Lifecycle", "lifecycle", "http://www.xes-standard.org/lifecycle.xesext"),
+            Extension::new("Organizational", "org", "http://www.xes-standard.org/org.xesext"),
+        ]
+    }
+}
+
+impl LogHeader {
+    /// Handles a header element of the XES document. Elements that are not part of the header are skipped.
+    pub(crate) fn read_element<R: BufRead>(
+        &mut self,
+        reader: &mut Reader<R>,
+        buf: &mut Vec<u8>,
+        element: &BytesStart,
+        is_empty: bool,
+        timestamps: &TimestampParser,
+    ) -> Result<()> {
+        match element.name().as_ref() {
+            b"log" => {
+                for (key, value) in element_attributes(reader, element)? {
+                    match key.as_str() {
+                        "xes.version" => self.version = Some(value),
+                        "xes.features" => self.features = Some(value),
+                        _ => (),
+                    }
+                }
+            }
+            b"extension" => {
+                let mut extension = Extension::new("", "", "");
+                for (key, value) in element_attributes(reader, element)? {
+                    match key.as_str() {
+                        "name" => extension.name = value,
+                        "prefix" => extension.prefix = value,
+                        "uri" => extension.uri = value,
+                        _ => (),
+                    }
+                }
+                self.extensions.push(extension);
+            }
+            b"classifier" => {
+                let mut classifier = Classifier {
+                    name: String::new(),
+                    keys: String::new(),
+                };
+                for (key, value) in element_attributes(reader, element)? {
+                    match key.as_str() {
+                        "name" => classifier.name = value,
+                        "keys" => classifier.keys = value,
+                        _ => (),
+                    }
+                }
+                self.classifiers.push(classifier);
+            }
+            b"global" => {
+                let scope = element_attributes(reader, element)?
+                    .into_iter()
+                    .find(|(key, _)| key == "scope")
+                    .map(|(_, value)| value);
+                let globals = if is_empty {
+                    Vec::new()
+                } else {
+                    read_globals(reader, buf, timestamps)?
+                };
+                match scope.as_deref() {
+                    Some("trace") => self.trace_globals.extend(globals),
+                    _ => self.event_globals.extend(globals),
+                }
+            }
+            name if is_attribute_tag(name) => {
+                let attribute = read_attribute(reader, buf, element, is_empty, timestamps)?;
+                self.attributes.insert(attribute);
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Writes the header elements. Without declared extensions, the standard ones are written instead.
+    pub(crate) fn write<W: Write>(&self, writer: &mut Writer<W>) -> quick_xml::Result<()> {
+        let standard_extensions = Extension::standard();
+        let extensions = if self.extensions.is_empty() {
+            &standard_extensions
+        } else {
+            &self.extensions
+        };
+        for extension in extensions {
+            writer
+                .create_element("extension")
+                .with_attribute(("name", extension.name.as_str()))
+                .with_attribute(("prefix", extension.prefix.as_str()))
+                .with_attribute(("uri", extension.uri.as_str()))
+                .write_empty()?;
+        }
+
+        for (scope, globals) in [("trace", &self.trace_globals), ("event", &self.event_globals)] {
+            if globals.is_empty() {
+                continue;
+            }
+            writer
+                .create_element("global")
+                .with_attribute(("scope", scope))
+                .write_inner_content(|w| {
+                    for attribute in globals {
+                        write_attribute(w, attribute)?;
+                    }
+                    Ok(())
+                })?;
+        }
+
+        for classifier in &self.classifiers {
+            writer
+                .create_element("classifier")
+                .with_attribute(("name", classifier.name.as_str()))
+                .with_attribute(("keys", classifier.keys.as_str()))
+                .write_empty()?;
+        }
+
+        for attribute in &self.attributes {
+            write_attribute(writer, attribute)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the attributes of a `<global>` element up to its end tag.
+fn read_globals<R: BufRead>(
+    reader: &mut Reader<R>,
+    buf: &mut Vec<u8>,
+    timestamps: &TimestampParser,
+) -> Result<Vec<Attribute>> {
+    let mut globals = Vec::new();
+    loop {
+        let (element, is_empty) = match read_event(reader, buf)? {
+            XmlEvent::Start(e) => (e.into_owned(), false),
+            XmlEvent::Empty(e) => (e.into_owned(), true),
+            XmlEvent::End(e) if e.name().as_ref() == b"global" => return Ok(globals),
+            XmlEvent::Eof => return Err(xml_error(reader, quick_xml::Error::UnexpectedEof("global".to_string()))),
+            _ => continue,
+        };
+        if is_attribute_tag(element.name().as_ref()) {
+            globals.push(read_attribute(reader, buf, &element, is_empty, timestamps)?);
+        }
+    }
+}
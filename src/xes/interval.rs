@@ -1,5 +1,8 @@
+use chrono::Duration;
 use serde::{Deserialize, Serialize};
 use crate::io;
+use crate::timestamp::{self, Timestamp, TimestampFormat};
+use crate::xes::attribute::Attributes;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct EventLog {
@@ -14,14 +17,124 @@ pub struct Trace {
     pub case: String,
     pub variant: String,
     pub events: Vec<Event>,
+
+    /// Trace attributes other than case and variant. Written to CSV as extra columns.
+    #[serde(skip)]
+    pub attributes: Attributes,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Event {
     pub activity: String,
     pub resource: String,
-    pub start_time: String,
-    pub end_time: String,
+    pub start_time: Timestamp,
+    pub end_time: Timestamp,
+
+    /// Time of the earliest `schedule` or `assign` transition before the start, if any.
+    pub scheduled_time: Option<Timestamp>,
+
+    /// Periods between `suspend` and `resume` transitions, which do not count as processing time.
+    pub suspensions: Vec<Suspension>,
+
+    /// How the activity instance ended.
+    pub completion: Completion,
+
+    /// Event attributes other than the mapped columns. Written to CSV as extra columns.
+    #[serde(skip)]
+    pub attributes: Attributes,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suspension {
+    pub suspend_time: Timestamp,
+    pub resume_time: Timestamp,
+}
+
+/// The lifecycle transition that ended an activity instance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Completion {
+    /// `complete`
+    #[default]
+    Complete,
+
+    /// `ate_abort`: the activity instance was aborted.
+    Aborted,
+
+    /// `pi_abort`: the whole process instance was aborted while the activity was running.
+    ProcessAborted,
+}
+
+impl Completion {
+    pub fn lifecycle_transition(&self) -> &'static str {
+        match self {
+            Completion::Complete => "complete",
+            Completion::Aborted => "ate_abort",
+            Completion::ProcessAborted => "pi_abort",
+        }
+    }
+
+    /// The completion of a `complete`, `ate_abort` or `pi_abort` transition in any case. An empty transition is
+    /// `complete`.
+    pub fn from_lifecycle_transition(transition: &str) -> Option<Completion> {
+        match transition.to_ascii_lowercase().as_str() {
+            "complete" | "" => Some(Completion::Complete),
+            "ate_abort" => Some(Completion::Aborted),
+            "pi_abort" => Some(Completion::ProcessAborted),
+            _ => None,
+        }
+    }
+}
+
+impl Event {
+    /// Time from start to end minus the time spent suspended.
+    pub fn processing_time(&self) -> Duration {
+        self.suspensions.iter().fold(self.end_time - self.start_time, |processing_time, suspension| {
+            processing_time - (suspension.resume_time - suspension.suspend_time)
+        })
+    }
+
+    /// Suspensions written by [`format_suspensions`], if each lies within the activity instance and ends after it
+    /// begins.
+    pub fn parse_suspensions(&self, value: &str) -> Option<Vec<Suspension>> {
+        value
+            .split_whitespace()
+            .map(|period| {
+                let (suspend_time, resume_time) = period.split_once('/')?;
+                let suspension = Suspension {
+                    suspend_time: TimestampFormat::Iso8601.parse(suspend_time)?,
+                    resume_time: TimestampFormat::Iso8601.parse(resume_time)?,
+                };
+                let within = self.start_time <= suspension.suspend_time
+                    && suspension.suspend_time <= suspension.resume_time
+                    && suspension.resume_time <= self.end_time;
+                within.then_some(suspension)
+            })
+            .collect()
+    }
+
+    /// A processing time given in seconds, if it is finite and not longer than the activity instance.
+    pub fn checked_processing_time(&self, seconds: f64) -> Option<Duration> {
+        if !seconds.is_finite() || seconds < 0.0 {
+            return None;
+        }
+        Duration::try_milliseconds((seconds * 1000.0).round() as i64)
+            .filter(|&processing_time| processing_time <= self.end_time - self.start_time)
+    }
+}
+
+/// Suspensions as ISO 8601 time intervals of the suspend and resume time, separated by spaces.
+pub fn format_suspensions(suspensions: &[Suspension]) -> String {
+    suspensions
+        .iter()
+        .map(|suspension| {
+            format!(
+                "{}/{}",
+                timestamp::format(&suspension.suspend_time),
+                timestamp::format(&suspension.resume_time)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 impl EventLog {
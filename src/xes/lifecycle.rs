@@ -1,192 +1,386 @@
-use std::io::Read;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::marker::PhantomData;
 use std::path::Path;
 
 use quick_xml::events::Event as XmlEvent;
 use quick_xml::Reader;
 
-#[derive(Debug, Clone)]
+use crate::compression;
+use crate::error::Result;
+use crate::io;
+use crate::timestamp::{Timestamp, TimestampParser};
+use crate::xes::attribute::{is_attribute_tag, read_attribute, read_event, Attribute, AttributeValue, Attributes};
+use crate::xes::header::LogHeader;
+
+#[derive(Debug, Clone, Default)]
 pub struct EventLog {
+    pub header: LogHeader,
     pub traces: Vec<Trace>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Trace {
     pub case: String,
     pub variant: String,
     pub events: Vec<Event>,
+
+    /// Trace attributes other than concept:name and variant, in document order.
+    pub attributes: Attributes,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Event {
     pub activity: String,
     pub resource: String,
-    pub timestamp: String,
+    pub timestamp: Option<Timestamp>,
     pub lifecycle: String,
+
+    /// Event attributes other than concept:name, org:resource, time:timestamp and lifecycle:transition, in
+    /// document order.
+    pub attributes: Attributes,
 }
 
-pub fn parse_file(file_name: &Path, filter_start_end_events: bool) -> EventLog {
-    let xml = std::fs::read_to_string(file_name).unwrap();
-    let mut reader = Reader::from_str(&xml);
-    reader.trim_text(true);
-
-    let mut event_log = EventLog { traces: Vec::new() };
-
-    let mut buf = Vec::new();
-    let mut current_trace = Trace {
-        case: String::new(),
-        variant: String::new(),
-        events: Vec::new(),
-    };
-    let mut current_event = Event {
-        activity: String::new(),
-        resource: String::new(),
-        timestamp: String::new(),
-        lifecycle: String::new(),
-    };
-    let mut in_trace = false;
-    let mut in_event = false;
-    let mut in_name = false;
-    let mut in_resource = false;
-    let mut in_timestamp = false;
-    let mut in_lifecycle = false;
-    let mut in_variant = false;
-    let mut skip_event = false;
-
-    loop {
-        match reader.expand_empty_elements(false).read_event_into(&mut buf) {
-            Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
-            Ok(XmlEvent::Eof) => break,
-
-            Ok(XmlEvent::Start(ref e)) => match e.name().as_ref() {
-                b"trace" => {
-                    in_trace = true;
-                    current_trace = Trace {
-                        case: String::new(),
-                        variant: String::new(),
-                        events: Vec::new(),
-                    };
-                }
+impl Trace {
+    pub(crate) fn set_attribute(&mut self, attribute: Attribute) {
+        match (attribute.key.as_str(), attribute.value.to_xes_value()) {
+            ("concept:name", Some(value)) => self.case = value,
+            ("variant", Some(value)) => self.variant = value,
+            _ => self.attributes.insert(attribute),
+        }
+    }
+}
 
-                b"event" => {
-                    in_event = true;
-                    current_event = Event {
-                        activity: String::new(),
-                        resource: String::new(),
-                        timestamp: String::new(),
-                        lifecycle: String::new(),
-                    };
-                }
+impl Event {
+    /// Whether the event is a `complete` transition in any case, or has no transition.
+    pub fn is_complete(&self) -> bool {
+        self.lifecycle.is_empty() || self.lifecycle.eq_ignore_ascii_case("complete")
+    }
 
-                _ => (),
+    pub(crate) fn set_attribute(&mut self, attribute: Attribute) {
+        match (attribute.key.as_str(), attribute.value.to_xes_value()) {
+            ("concept:name", Some(value)) => self.activity = value,
+            ("org:resource", Some(value)) => self.resource = value,
+            ("lifecycle:transition", Some(value)) => self.lifecycle = value,
+            _ => match (attribute.key.as_str(), &attribute.value) {
+                ("time:timestamp", AttributeValue::Date(timestamp)) => self.timestamp = Some(*timestamp),
+                _ => self.attributes.insert(attribute),
             },
+        }
+    }
+}
 
-            Ok(XmlEvent::Empty(ref e)) => match e.name().as_ref() {
-                b"string" | b"date" => {
-                    e.attributes().for_each(|a| {
-                        let a = a.unwrap();
-                        let mut key = String::new();
-                        let mut value = String::new();
-                        let _ = a.key.as_ref().read_to_string(&mut key);
-                        let _ = a.value.as_ref().read_to_string(&mut value);
-
-                        if key == "key" {
-                            match value.as_str() {
-                                "concept:name" => {
-                                    in_name = true;
-                                    in_resource = false;
-                                    in_timestamp = false;
-                                    in_lifecycle = false;
-                                    in_variant = false;
-                                }
-                                "org:resource" => {
-                                    in_name = false;
-                                    in_resource = true;
-                                    in_timestamp = false;
-                                    in_lifecycle = false;
-                                    in_variant = false;
-                                }
-                                "time:timestamp" => {
-                                    in_name = false;
-                                    in_resource = false;
-                                    in_timestamp = true;
-                                    in_lifecycle = false;
-                                    in_variant = false;
-                                }
-                                "lifecycle:transition" => {
-                                    in_name = false;
-                                    in_resource = false;
-                                    in_timestamp = false;
-                                    in_lifecycle = true;
-                                    in_variant = false;
-                                }
-                                "variant" => {
-                                    in_name = false;
-                                    in_resource = false;
-                                    in_timestamp = false;
-                                    in_lifecycle = false;
-                                    in_variant = true;
-                                }
-                                _ => {
-                                    in_name = false;
-                                    in_resource = false;
-                                    in_timestamp = false;
-                                    in_lifecycle = false;
-                                    in_variant = false;
-                                }
-                            }
-                        }
+/// Reads a whole XES file, which may be gzip or Zstandard compressed.
+pub fn parse_file(file_name: &Path, filter_start_end_events: bool) -> Result<EventLog> {
+    let file = compression::decompress(File::open(file_name)?)?;
+    let mut reader = TraceReader::new(BufReader::new(file), filter_start_end_events);
+    let header = reader.header()?.clone();
+
+    Ok(EventLog {
+        header,
+        traces: reader.collect::<Result<Vec<_>>>()?,
+    })
+}
+
+/// Streaming reader of an XML event log that yields one trace at a time, so memory use is bounded by the largest
+/// trace. The elements of the log are read by its [`TraceFormat`], and the header is everything before the first
+/// trace.
+pub struct XmlTraceReader<R: BufRead, F: TraceFormat> {
+    pub(crate) reader: Reader<R>,
+    pub(crate) buf: Vec<u8>,
+    pub(crate) timestamps: TimestampParser,
+    pub(crate) header: LogHeader,
+
+    /// Set once the first trace or the end of the log is reached, after which the header is complete.
+    pub(crate) header_read: bool,
+    filter_start_end_events: bool,
+    peeked: Option<Trace>,
+    done: bool,
+    format: PhantomData<F>,
+}
 
-                        if key == "value" {
-                            if in_name {
-                                if filter_start_end_events {
-                                    let v = value.to_lowercase();
-                                    if v == "start" || v == "end" {
-                                        skip_event = true;
-                                    }
-                                }
-
-                                if in_event {
-                                    current_event.activity = value;
-                                } else if in_trace {
-                                    current_trace.case = value;
-                                }
-                            } else if in_resource && in_event {
-                                current_event.resource = value;
-                            } else if in_timestamp && in_event {
-                                current_event.timestamp = value;
-                            } else if in_lifecycle && in_event {
-                                current_event.lifecycle = value;
-                            } else if in_variant && in_trace {
-                                current_trace.variant = value;
+/// An XML event log format, which reads the elements of the next trace and the header elements before it.
+pub trait TraceFormat: Sized {
+    /// Reads up to the end of the next trace, or returns `None` at the end of the log.
+    fn next_trace<R: BufRead>(reader: &mut XmlTraceReader<R, Self>) -> Result<Option<Trace>>;
+}
+
+impl<R: BufRead, F: TraceFormat> XmlTraceReader<R, F> {
+    pub fn new(reader: R, filter_start_end_events: bool) -> XmlTraceReader<R, F> {
+        let mut reader = Reader::from_reader(reader);
+        reader.trim_text(true);
+        reader.expand_empty_elements(false);
+
+        XmlTraceReader {
+            reader,
+            buf: Vec::new(),
+            timestamps: TimestampParser::default(),
+            header: LogHeader::default(),
+            header_read: false,
+            filter_start_end_events,
+            peeked: None,
+            done: false,
+            format: PhantomData,
+        }
+    }
+
+    /// Sets the timezones for timestamps. They are always ISO 8601, so the formats are ignored.
+    pub fn with_timestamps(mut self, timestamps: TimestampParser) -> XmlTraceReader<R, F> {
+        self.timestamps = TimestampParser {
+            formats: TimestampParser::default().formats,
+            ..timestamps
+        };
+        self
+    }
+
+    /// Returns the log header, reading ahead to the first trace if needed.
+    pub fn header(&mut self) -> Result<&LogHeader> {
+        if !self.header_read && !self.done {
+            self.peeked = F::next_trace(self)?;
+            self.done = self.peeked.is_none();
+        }
+        Ok(&self.header)
+    }
+
+    /// Adds an event to a trace, unless it is an artificial start or end event that is filtered out.
+    pub(crate) fn push_event(&self, trace: &mut Trace, event: Event) {
+        let activity = event.activity.to_lowercase();
+        let is_start_end = activity == "start" || activity == "end";
+        if !(self.filter_start_end_events && is_start_end) {
+            trace.events.push(event);
+        }
+    }
+}
+
+impl<R: BufRead, F: TraceFormat> Iterator for XmlTraceReader<R, F> {
+    type Item = Result<Trace>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(trace) = self.peeked.take() {
+            return Some(Ok(trace));
+        }
+        if self.done {
+            return None;
+        }
+        let next = F::next_trace(self);
+        io::iterator_item(&mut self.done, next)
+    }
+}
+
+/// The XES format.
+pub struct Xes;
+
+/// Streaming XES reader that yields one trace at a time.
+pub type TraceReader<R> = XmlTraceReader<R, Xes>;
+
+impl TraceFormat for Xes {
+    fn next_trace<R: BufRead>(reader: &mut TraceReader<R>) -> Result<Option<Trace>> {
+        let mut current_trace: Option<Trace> = None;
+        let mut current_event: Option<Event> = None;
+
+        loop {
+            let (element, is_empty) = match read_event(&mut reader.reader, &mut reader.buf)? {
+                XmlEvent::Eof => {
+                    reader.header_read = true;
+                    return Ok(None);
+                }
+                XmlEvent::Start(e) => (e.into_owned(), false),
+                XmlEvent::Empty(e) => (e.into_owned(), true),
+                XmlEvent::End(e) => {
+                    match e.name().as_ref() {
+                        b"trace" => return Ok(current_trace),
+                        b"event" => {
+                            if let (Some(trace), Some(event)) = (current_trace.as_mut(), current_event.take()) {
+                                reader.push_event(trace, event);
                             }
                         }
-                    });
+                        _ => (),
+                    }
+                    continue;
                 }
+                _ => continue,
+            };
 
-                _ => (),
-            },
-
-            Ok(XmlEvent::End(ref e)) => match e.name().as_ref() {
+            match element.name().as_ref() {
                 b"trace" => {
-                    in_trace = false;
-                    event_log.traces.push(current_trace.clone());
+                    reader.header_read = true;
+                    if is_empty {
+                        return Ok(Some(Trace::default()));
+                    }
+                    current_trace = Some(Trace::default());
                 }
-                b"event" => {
-                    in_event = false;
-                    if !skip_event {
-                        current_trace.events.push(current_event.clone());
-                    } else {
-                        skip_event = false;
+                b"event" if current_trace.is_some() && !is_empty => {
+                    current_event = Some(Event::default());
+                }
+                name if current_trace.is_some() && is_attribute_tag(name) => {
+                    let attribute =
+                        read_attribute(&mut reader.reader, &mut reader.buf, &element, is_empty, &reader.timestamps)?;
+                    if let Some(event) = current_event.as_mut() {
+                        event.set_attribute(attribute);
+                    } else if let Some(trace) = current_trace.as_mut() {
+                        trace.set_attribute(attribute);
                     }
                 }
+                _ if current_trace.is_none() => {
+                    reader
+                        .header
+                        .read_element(&mut reader.reader, &mut reader.buf, &element, is_empty, &reader.timestamps)?;
+                }
                 _ => (),
-            },
-
-            _ => (),
+            }
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::timestamp;
+    use crate::xes::attribute::AttributeValue;
+
+    use super::*;
+
+    #[test]
+    fn test_trace_reader() {
+        let xes = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <log>
+            <trace>
+                <string key="concept:name" value="1"/>
+                <event>
+                    <string key="concept:name" value="A"/>
+                    <string key="lifecycle:transition" value="complete"/>
+                </event>
+            </trace>
+            <trace>
+                <string key="concept:name" value="2"/>
+            </trace>
+        </log>"#;
+
+        let mut reader = TraceReader::new(xes.as_bytes(), false);
 
-        buf.clear();
+        let first = reader.next().unwrap().unwrap();
+        assert_eq!(first.case, "1");
+        assert_eq!(first.events.len(), 1);
+        assert_eq!(first.events[0].activity, "A");
+
+        let second = reader.next().unwrap().unwrap();
+        assert_eq!(second.case, "2");
+        assert!(second.events.is_empty());
+
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_trace_reader_attributes() {
+        let xes = r#"<log>
+            <trace>
+                <string key="concept:name" value="1"/>
+                <float key="cost" value="10.5"/>
+                <event>
+                    <string key="concept:name" value="A &amp; B"/>
+                    <int key="priority" value="2"/>
+                    <boolean key="urgent" value="true"/>
+                    <string key="org:role" value="clerk">
+                        <id key="role:id" value="r-1"/>
+                    </string>
+                    <list key="tags">
+                        <values>
+                            <string key="tag" value="x"/>
+                            <string key="tag" value="y"/>
+                        </values>
+                    </list>
+                    <container key="address">
+                        <string key="city" value="Tartu"/>
+                    </container>
+                </event>
+            </trace>
+        </log>"#;
+
+        let trace = TraceReader::new(xes.as_bytes(), false).next().unwrap().unwrap();
+
+        assert_eq!(trace.attributes.get("cost").unwrap().value, AttributeValue::Float(10.5));
+
+        let event = &trace.events[0];
+        assert_eq!(event.activity, "A & B");
+        let keys = event.attributes.iter().map(|a| a.key.as_str()).collect::<Vec<_>>();
+        assert_eq!(keys, ["priority", "urgent", "org:role", "tags", "address"]);
+        assert_eq!(event.attributes.get("priority").unwrap().value, AttributeValue::Int(2));
+        assert_eq!(
+            event.attributes.get("urgent").unwrap().value,
+            AttributeValue::Boolean(true)
+        );
+        assert_eq!(
+            event.attributes.get("org:role").unwrap().children,
+            vec![Attribute::new("role:id", AttributeValue::Id("r-1".to_string()))]
+        );
+        assert!(matches!(&event.attributes.get("tags").unwrap().value, AttributeValue::List(v) if v.len() == 2));
+        assert!(
+            matches!(&event.attributes.get("address").unwrap().value, AttributeValue::Container(v) if v.len() == 1)
+        );
+    }
+
+    #[test]
+    fn test_trace_reader_invalid_attribute() {
+        let xes = r#"<log><trace><event><int key="priority" value="high"/></event></trace></log>"#;
+
+        let result = TraceReader::new(xes.as_bytes(), false).next().unwrap();
+
+        assert!(matches!(result, Err(Error::InvalidAttribute { key, .. }) if key == "priority"));
+    }
+
+    #[test]
+    fn test_trace_reader_timezones() {
+        let xes = r#"<log><trace><event>
+            <date key="time:timestamp" value="2022-07-01T09:10:00"/>
+        </event><event>
+            <date key="time:timestamp" value="2022-10-30T02:30:00"/>
+        </event></trace></log>"#;
+        let timestamps = TimestampParser {
+            input_timezone: chrono_tz::Europe::Berlin,
+            output_timezone: Some(chrono_tz::Tz::UTC),
+            ..Default::default()
+        };
+
+        let result = TraceReader::new(xes.as_bytes(), false).with_timestamps(timestamps.clone()).next().unwrap();
+
+        assert!(matches!(result, Err(Error::LocalTime { value, .. }) if value == "2022-10-30T02:30:00"));
+
+        let xes = xes.replace("2022-10-30T02:30:00", "2022-10-30T03:30:00+01:00");
+        let trace = TraceReader::new(xes.as_bytes(), false).with_timestamps(timestamps).next().unwrap().unwrap();
+
+        let timestamps = trace.events.iter().map(|e| timestamp::format(&e.timestamp.unwrap())).collect::<Vec<_>>();
+        assert_eq!(timestamps, ["2022-07-01T07:10:00.000+00:00", "2022-10-30T02:30:00.000+00:00"]);
     }
 
-    event_log
+    #[test]
+    fn test_trace_reader_header() {
+        let xes = r#"<log xes.version="1.0">
+            <extension name="Concept" prefix="concept" uri="http://www.xes-standard.org/concept.xesext"/>
+            <global scope="trace">
+                <string key="concept:name" value="__INVALID__"/>
+            </global>
+            <global scope="event">
+                <string key="concept:name" value="__INVALID__"/>
+                <date key="time:timestamp" value="1970-01-01T00:00:00.000+00:00"/>
+            </global>
+            <classifier name="Activity" keys="concept:name"/>
+            <string key="concept:name" value="Production"/>
+            <trace>
+                <string key="concept:name" value="1"/>
+            </trace>
+        </log>"#;
+
+        let mut reader = TraceReader::new(xes.as_bytes(), false);
+        let header = reader.header().unwrap().clone();
+
+        assert_eq!(header.version.as_deref(), Some("1.0"));
+        assert_eq!(header.extensions.len(), 1);
+        assert_eq!(header.extensions[0].prefix, "concept");
+        assert_eq!(header.trace_globals.len(), 1);
+        assert_eq!(header.event_globals.len(), 2);
+        assert_eq!(header.classifiers[0].keys, "concept:name");
+        assert!(header.attributes.get("concept:name").is_some());
+        assert_eq!(reader.next().unwrap().unwrap().case, "1");
+        assert!(reader.next().is_none());
+    }
 }